@@ -1,18 +1,20 @@
-fn main() {
+fn main() -> std::io::Result<()> {
     println!("Families");
     println!("--------");
-    for fam in appx::repository::families().unwrap() {
+    for fam in appx::repository::families()? {
+        let fam = fam?;
         println!("{}", fam);
-        for pkg in appx::repository::packages_for_family(&fam).unwrap() {
-            println!("    {}", pkg);
+        for pkg in appx::repository::packages_for_family(&fam)? {
+            println!("    {}", pkg?);
         }
     }
     println!();
 
     println!("Packages");
     println!("--------");
-    for pkg in appx::repository::packages().unwrap() {
-        println!("{}", pkg);
+    for pkg in appx::repository::packages()? {
+        println!("{}", pkg?);
     }
     println!();
+    Ok(())
 }
@@ -8,8 +8,28 @@
 
 #[macro_use] mod macros;
 
+mod architecture;           pub use architecture::{ProcessorArchitecture, ProcessorArchitectureError};
+mod aumid;                  pub use aumid::ApplicationUserModelId;
+mod capability;             pub use capability::{Capability, CapabilityNamespace, CapabilitySidsError, derived_capability_sid, parse_capability_sids};
+pub mod command;
+mod error;                  pub use error::AppxError;
 mod family;                 pub use family::PackageFamilyName;
+mod hive;
+mod info;                   pub use info::PackageInfo;
+mod id;                     pub use id::{PackageId, PackageIdBuilder};
+mod ordinal;
 mod package;                pub use package::PackageFullName;
+pub mod powershell;
+mod publisher;              pub use publisher::{PublisherId, PublisherIdError};
+mod query;                  pub use query::PackageQuery;
 mod reg;
+pub mod registry;
 pub mod repository;
+mod resource;               pub use resource::{ResourceId, ResourceQualifier};
+mod sid;                    pub use sid::{Sid, SidError};
+mod snapshot;               pub use snapshot::{FamilyDiff, PackageSnapshot, RepositoryDiff, RepositorySnapshot};
+mod validate;               pub use validate::PackageIdError;
+mod version;                pub use version::{PackageVersion, PackageVersionError};
+pub mod watch;
+mod wine;
 mod wstring;                pub use wstring::WString;
@@ -0,0 +1,166 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+
+
+/// e.g. `x64` -
+/// The processor architecture an appx package targets.
+///
+/// ### Corresponds to
+///
+/// `powershell Get-AppxPackage ^| Format-Table -Property Architecture`<br>
+/// [Windows::System::ProcessorArchitecture](https://docs.microsoft.com/en-us/uwp/api/windows.system.processorarchitecture)<br>
+/// [Identity@ProcessorArchitecture](https://docs.microsoft.com/en-us/uwp/schemas/appxpackage/appxmanifestschema/element-identity)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProcessorArchitecture {
+    /// `x86` - 32-bit Intel/AMD
+    X86,
+    /// `x64` - 64-bit Intel/AMD
+    X64,
+    /// `arm` - 32-bit ARM
+    Arm,
+    /// `arm64` - 64-bit ARM
+    Arm64,
+    /// `x86a64` - x86 code running on 64-bit ARM
+    X86A64,
+    /// `neutral` - architecture independent
+    Neutral,
+    /// `unknown`
+    Unknown,
+}
+
+impl ProcessorArchitecture {
+    /// All architectures, including [Unknown](Self::Unknown)
+    pub const ALL : &'static [ProcessorArchitecture] = &[Self::X86, Self::X64, Self::Arm, Self::Arm64, Self::X86A64, Self::Neutral, Self::Unknown];
+
+    /// The lowercase name used in package full names (e.g. `x64`, `neutral`)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::X86       => "x86",
+            Self::X64       => "x64",
+            Self::Arm       => "arm",
+            Self::Arm64     => "arm64",
+            Self::X86A64    => "x86a64",
+            Self::Neutral   => "neutral",
+            Self::Unknown   => "unknown",
+        }
+    }
+
+    /// The [Windows::System::ProcessorArchitecture](https://docs.microsoft.com/en-us/uwp/api/windows.system.processorarchitecture) value (e.g. `9` for `x64`)
+    pub fn to_u32(self) -> u32 {
+        match self {
+            Self::X86       => 0,
+            Self::Arm       => 5,
+            Self::X64       => 9,
+            Self::Neutral   => 11,
+            Self::Arm64     => 12,
+            Self::X86A64    => 14,
+            Self::Unknown   => 65535,
+        }
+    }
+
+    /// Convert from a [Windows::System::ProcessorArchitecture](https://docs.microsoft.com/en-us/uwp/api/windows.system.processorarchitecture) value
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            0   => Self::X86,
+            5   => Self::Arm,
+            9   => Self::X64,
+            11  => Self::Neutral,
+            12  => Self::Arm64,
+            14  => Self::X86A64,
+            _   => Self::Unknown,
+        }
+    }
+
+    /// Parse [code units](https://unicode.org/glossary/#code_unit) (case insensitive), or [None] if unrecognized
+    pub fn from_units(units: &[u16]) -> Option<Self> {
+        Self::ALL.iter().copied().find(|arch| {
+            let name = arch.as_str().as_bytes();
+            name.len() == units.len() && name.iter().zip(units.iter()).all(|(&n, &u)| u < 0x80 && (u as u8).to_ascii_lowercase() == n)
+        })
+    }
+
+    /// Can a package targeting `self` run on a machine with a `machine` native architecture?
+    ///
+    /// * `neutral` packages run everywhere
+    /// * `x64` machines also run `x86` packages
+    /// * `arm64` machines also run `arm`, `x86`, `x64` and `x86a64` packages (via emulation)
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use appx::ProcessorArchitecture::*;
+    /// assert!( X86.can_run_on(X64));
+    /// assert!( X64.can_run_on(Arm64));
+    /// assert!(!X64.can_run_on(X86));
+    /// assert!( Neutral.can_run_on(Arm));
+    /// ```
+    pub fn can_run_on(self, machine: ProcessorArchitecture) -> bool {
+        Self::applicable_to(machine).contains(&self)
+    }
+
+    /// Package architectures that can run on a machine with a `machine` native architecture, most preferred first.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use appx::ProcessorArchitecture::*;
+    /// assert_eq!(appx::ProcessorArchitecture::applicable_to(X64), &[X64, X86, Neutral]);
+    /// ```
+    pub fn applicable_to(machine: ProcessorArchitecture) -> &'static [ProcessorArchitecture] {
+        match machine {
+            Self::X86       => &[Self::X86, Self::Neutral],
+            Self::X64       => &[Self::X64, Self::X86, Self::Neutral],
+            Self::Arm       => &[Self::Arm, Self::Neutral],
+            Self::Arm64     => &[Self::Arm64, Self::X64, Self::X86A64, Self::X86, Self::Arm, Self::Neutral],
+            Self::X86A64    => &[Self::X86A64, Self::X86, Self::Neutral],
+            Self::Neutral   => &[Self::Neutral],
+            Self::Unknown   => &[Self::Neutral],
+        }
+    }
+}
+
+impl Display            for ProcessorArchitecture { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { fmt.write_str(self.as_str()) } }
+impl FromStr            for ProcessorArchitecture { fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_units(&s.encode_utf16().collect::<Vec<u16>>()).ok_or(ProcessorArchitectureError(())) } type Err = ProcessorArchitectureError; }
+impl TryFrom<&[u16]>    for ProcessorArchitecture { fn try_from(units: &[u16]) -> Result<Self, Self::Error> { Self::from_units(units).ok_or(ProcessorArchitectureError(())) } type Error = ProcessorArchitectureError; }
+impl From<ProcessorArchitecture> for u32          { fn from(arch: ProcessorArchitecture) -> Self { arch.to_u32() } }
+
+
+
+/// A [ProcessorArchitecture] failed to parse
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ProcessorArchitectureError(pub(crate) ());
+
+impl Error   for ProcessorArchitectureError {}
+impl Display for ProcessorArchitectureError { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { fmt.write_str("unrecognized processor architecture") } }
+
+
+
+#[test] fn test_architecture() {
+    use ProcessorArchitecture::*;
+
+    for &arch in ProcessorArchitecture::ALL {
+        assert_eq!(arch.to_string().parse::<ProcessorArchitecture>(), Ok(arch));
+        assert_eq!(ProcessorArchitecture::from_u32(arch.to_u32()), arch);
+        assert!(Neutral.can_run_on(arch));
+    }
+    assert_eq!("X64".parse::<ProcessorArchitecture>(), Ok(X64));
+    assert!("x65".parse::<ProcessorArchitecture>().is_err());
+    assert!("".parse::<ProcessorArchitecture>().is_err());
+    assert_eq!(ProcessorArchitecture::from_units(wchar::wch!("arm64")), Some(Arm64));
+    assert_eq!(ProcessorArchitecture::from_units(&[u16::from(b'x') + 0x100, u16::from(b'6'), u16::from(b'4')]), None);
+
+    assert!( X86   .can_run_on(X86));
+    assert!( X86   .can_run_on(X64));
+    assert!( X86   .can_run_on(Arm64));
+    assert!(!X86   .can_run_on(Arm));
+    assert!( X64   .can_run_on(Arm64));
+    assert!(!X64   .can_run_on(X86));
+    assert!( Arm   .can_run_on(Arm64));
+    assert!(!Arm64 .can_run_on(X64));
+    assert!( X86A64.can_run_on(Arm64));
+    assert!(!X86A64.can_run_on(X64));
+    assert!(!Unknown.can_run_on(X64));
+}
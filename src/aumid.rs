@@ -0,0 +1,140 @@
+use crate::{ordinal, validate};
+use crate::{PackageFamilyName, PackageIdError, WString};
+
+use std::cmp::Ordering;
+use std::convert::{TryFrom, TryInto};
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::str::FromStr;
+
+
+
+/// e.g. `Microsoft.WindowsCalculator_8wekyb3d8bbwe!App` -
+/// Identifies a specific application within a package family, for launching, pinning, notifications, etc.
+///
+/// This is comprised of the following `!` separated fields:
+///
+/// | Field                 | Example                                   | Example |
+/// | --------------------- | ----------------------------------------- | ------- |
+/// | `PackageFamilyName`   | `Microsoft.WindowsCalculator_8wekyb3d8bbwe` | `CanonicalGroupLimited.UbuntuonWindows_79rhkp1fndgsc`
+/// | `ApplicationId`       | `App`                                     | `ubuntu`
+///
+/// ### Examples
+///
+/// ```rust
+/// let aumid = appx::ApplicationUserModelId::new("Microsoft.WindowsCalculator_8wekyb3d8bbwe!App").unwrap();
+/// assert_eq!(aumid.family_name().to_string(), "Microsoft.WindowsCalculator_8wekyb3d8bbwe");
+/// assert_eq!(aumid.app_id(), wchar::wch!("App"));
+/// ```
+///
+/// ### Comparisons
+///
+/// Like [PackageFamilyName], [PartialEq], [Ord] and [Hash] ignore case.
+///
+/// ### Corresponds to
+///
+/// `explorer shell:AppsFolder\Microsoft.WindowsCalculator_8wekyb3d8bbwe!App`<br>
+/// [Application User Model IDs](https://docs.microsoft.com/en-us/windows/win32/shell/appids)<br>
+/// [Application@Id](https://docs.microsoft.com/en-us/uwp/schemas/appxpackage/uapmanifestschema/element-application)
+#[derive(Clone, Debug)]
+pub struct ApplicationUserModelId {
+    aumid:  WString,
+    bang:   usize, // index of the `!` within `aumid`
+}
+
+impl Deref              for ApplicationUserModelId { fn deref(&self) -> &Self::Target { &self.aumid } type Target = WString; }
+impl Display            for ApplicationUserModelId { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { Display::fmt(&self.aumid.display(), fmt) } }
+impl PartialEq          for ApplicationUserModelId { fn eq(&self, other: &Self) -> bool { ordinal::eq_ignore_case(self.aumid.units(), other.aumid.units()) } }
+impl Eq                 for ApplicationUserModelId {}
+impl PartialOrd         for ApplicationUserModelId { fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) } }
+impl Ord                for ApplicationUserModelId { fn cmp(&self, other: &Self) -> Ordering { ordinal::cmp_ignore_case(self.aumid.units(), other.aumid.units()) } }
+impl Hash               for ApplicationUserModelId { fn hash<H: Hasher>(&self, state: &mut H) { ordinal::hash_ignore_case(self.aumid.units(), state) } }
+
+impl FromStr            for ApplicationUserModelId { fn from_str(value: &str) -> Result<Self, Self::Err> { Self::checked(WString::from(value)) } type Err = PackageIdError; }
+impl TryFrom<&String  > for ApplicationUserModelId { fn try_from(value: &String  ) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom< String  > for ApplicationUserModelId { fn try_from(value:  String  ) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&str     > for ApplicationUserModelId { fn try_from(value: &str     ) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&   [u16]> for ApplicationUserModelId { fn try_from(value: &   [u16]) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&Vec<u16>> for ApplicationUserModelId { fn try_from(value: &Vec<u16>) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom< Vec<u16>> for ApplicationUserModelId { fn try_from(value:  Vec<u16>) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&WString > for ApplicationUserModelId { fn try_from(value: &WString ) -> Result<Self, Self::Error> { Self::checked(value.clone())       } type Error = PackageIdError; }
+impl TryFrom< WString > for ApplicationUserModelId { fn try_from(value:  WString ) -> Result<Self, Self::Error> { Self::checked(value)               } type Error = PackageIdError; }
+impl From<&ApplicationUserModelId> for WString     { fn from(value: &ApplicationUserModelId) -> Self { value.aumid.clone() } }
+impl From< ApplicationUserModelId> for WString     { fn from(value:  ApplicationUserModelId) -> Self { value.aumid } }
+
+impl ApplicationUserModelId {
+    /// Parse and validate an [ApplicationUserModelId] (e.g. `Microsoft.WindowsCalculator_8wekyb3d8bbwe!App`)
+    pub fn new(aumid: impl TryInto<Self, Error = PackageIdError>) -> Result<Self, PackageIdError> { aumid.try_into() }
+
+    /// Combine a [PackageFamilyName] and a package relative application ID (e.g. `App`), validating the application ID
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use appx::*;
+    /// let fam = PackageFamilyName::new("Microsoft.WindowsCalculator_8wekyb3d8bbwe").unwrap();
+    /// let aumid = ApplicationUserModelId::from_parts(&fam, "App").unwrap();
+    /// assert_eq!(aumid.to_string(), "Microsoft.WindowsCalculator_8wekyb3d8bbwe!App");
+    /// ```
+    pub fn from_parts(family: &PackageFamilyName, app_id: &str) -> Result<Self, PackageIdError> {
+        let app_id = app_id.encode_utf16().collect::<Vec<u16>>();
+        validate::app_id(&app_id)?;
+        let bang = family.units().len();
+        let mut aumid = family.units().to_vec();
+        aumid.push(u16::from(b'!'));
+        aumid.extend_from_slice(&app_id);
+        Ok(Self { aumid: WString::from(aumid), bang })
+    }
+
+    /// Construct an [ApplicationUserModelId] **without** validating it (e.g. for IDs read from the registry or shell).
+    /// If there's no `!`, the whole thing is treated as the family name.
+    pub fn new_unchecked(aumid: impl Into<WString>) -> Self {
+        let aumid = aumid.into();
+        let bang = aumid.units().iter().position(|&cu| cu == u16::from(b'!')).unwrap_or(aumid.len());
+        Self { aumid, bang }
+    }
+
+    fn checked(aumid: WString) -> Result<Self, PackageIdError> {
+        let aumid = Self::new_unchecked(aumid);
+        aumid.validate()?;
+        Ok(aumid)
+    }
+
+    /// Check that this is a valid `PackageFamilyName!ApplicationId`
+    pub fn validate(&self) -> Result<(), PackageIdError> {
+        if self.aumid.units().iter().filter(|&&cu| cu == u16::from(b'!')).count() != 1 { return Err(PackageIdError::AppIdSeparator); }
+        self.family_name().validate()?;
+        validate::app_id(self.app_id())
+    }
+
+    /// The [PackageFamilyName] (e.g. `Microsoft.WindowsCalculator_8wekyb3d8bbwe`)
+    pub fn family_name(&self) -> PackageFamilyName { PackageFamilyName::new_unchecked(&self.aumid.units()[..self.bang]) }
+
+    /// The package relative application ID (e.g. `App`), or `&[]` if missing
+    pub fn app_id(&self) -> &[u16] { self.aumid.units().get(self.bang+1..).unwrap_or(&[]) }
+}
+
+
+
+#[test] fn test_aumid() {
+    let aumid = ApplicationUserModelId::new("Microsoft.WindowsCalculator_8wekyb3d8bbwe!App").unwrap();
+    assert_eq!(aumid.family_name(), PackageFamilyName::new("Microsoft.WindowsCalculator_8wekyb3d8bbwe").unwrap());
+    assert_eq!(aumid.app_id(), wchar::wch!("App"));
+    assert_eq!(aumid.to_string(), "Microsoft.WindowsCalculator_8wekyb3d8bbwe!App");
+    assert_eq!(WString::from(&aumid), WString::from("Microsoft.WindowsCalculator_8wekyb3d8bbwe!App"));
+    assert_eq!(ApplicationUserModelId::try_from(WString::from(&aumid)), Ok(aumid.clone()));
+    assert_eq!(ApplicationUserModelId::try_from(wchar::wch!("Microsoft.WindowsCalculator_8wekyb3d8bbwe!App").to_vec()), Ok(aumid.clone()));
+    assert_eq!(ApplicationUserModelId::from_parts(&aumid.family_name(), "App"), Ok(aumid.clone()));
+    assert_eq!(aumid, "microsoft.windowscalculator_8wekyb3d8bbwe!app".parse().unwrap());
+
+    assert_eq!(ApplicationUserModelId::new("Microsoft.WindowsCalculator_8wekyb3d8bbwe"),            Err(PackageIdError::AppIdSeparator));
+    assert_eq!(ApplicationUserModelId::new("Microsoft.WindowsCalculator_8wekyb3d8bbwe!App!App"),    Err(PackageIdError::AppIdSeparator));
+    assert_eq!(ApplicationUserModelId::new("Microsoft.WindowsCalculator_8wekyb3d8bbwe!"),           Err(PackageIdError::AppId));
+    assert_eq!(ApplicationUserModelId::new("Microsoft.WindowsCalculator_8wekyb3d8bbwe!1App"),       Err(PackageIdError::AppId));
+    assert_eq!(ApplicationUserModelId::new("Microsoft.WindowsCalculator!App"),                      Err(PackageIdError::FieldCount { expected: 2, found: 1 }));
+
+    let unchecked = ApplicationUserModelId::new_unchecked("garbage");
+    assert_eq!(unchecked.family_name(), PackageFamilyName::new_unchecked("garbage"));
+    assert_eq!(unchecked.app_id(), &[] as &[u16]);
+}
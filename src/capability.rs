@@ -0,0 +1,301 @@
+use crate::{ordinal, Sid};
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+
+
+/// e.g. `internetClient`, `uap:picturesLibrary`, `rescap:runFullTrust` -
+/// A known [app capability](https://docs.microsoft.com/en-us/windows/uwp/packaging/app-capability-declarations).
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::*;
+/// let cap = Capability::find("internetClient").unwrap();
+/// assert_eq!(cap.sid().to_string(), "S-1-15-3-1");
+/// assert_eq!(Capability::from_sid(&cap.sid()), Some(cap));
+///
+/// let cap = Capability::find("runFullTrust").unwrap();
+/// assert_eq!(cap.namespace(), CapabilityNamespace::Rescap);
+/// assert!(cap.is_restricted());
+/// assert!(cap.sid().to_string().starts_with("S-1-15-3-1024-"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Capability {
+    name:           &'static str,
+    namespace:      CapabilityNamespace,
+    restricted:     bool,
+    well_known_rid: Option<u32>,
+}
+
+/// The XML namespace a [Capability] is declared in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum CapabilityNamespace {
+    /// `<Capability Name="..."/>` (no prefix)
+    Foundation,
+    /// `<uap:Capability Name="..."/>`
+    Uap,
+    /// `<uap2:Capability Name="..."/>`
+    Uap2,
+    /// `<uap3:Capability Name="..."/>`
+    Uap3,
+    /// `<uap4:Capability Name="..."/>`
+    Uap4,
+    /// `<uap6:Capability Name="..."/>`
+    Uap6,
+    /// `<uap7:Capability Name="..."/>`
+    Uap7,
+    /// `<uap11:Capability Name="..."/>`
+    Uap11,
+    /// `<rescap:Capability Name="..."/>` - restricted capabilities
+    Rescap,
+    /// `<DeviceCapability Name="..."/>`
+    Device,
+}
+
+impl CapabilityNamespace {
+    /// The conventional XML prefix (e.g. `uap`, `rescap`), or [None] for [Foundation](Self::Foundation) and [Device](Self::Device) capabilities
+    pub fn prefix(self) -> Option<&'static str> {
+        match self {
+            CapabilityNamespace::Foundation => None,
+            CapabilityNamespace::Uap        => Some("uap"),
+            CapabilityNamespace::Uap2       => Some("uap2"),
+            CapabilityNamespace::Uap3       => Some("uap3"),
+            CapabilityNamespace::Uap4       => Some("uap4"),
+            CapabilityNamespace::Uap6       => Some("uap6"),
+            CapabilityNamespace::Uap7       => Some("uap7"),
+            CapabilityNamespace::Uap11      => Some("uap11"),
+            CapabilityNamespace::Rescap     => Some("rescap"),
+            CapabilityNamespace::Device     => None,
+        }
+    }
+}
+
+const fn cap(name: &'static str, namespace: CapabilityNamespace, restricted: bool, well_known_rid: Option<u32>) -> Capability {
+    Capability { name, namespace, restricted, well_known_rid }
+}
+
+use CapabilityNamespace::*;
+
+const CATALOG : &[Capability] = &[
+    // https://docs.microsoft.com/en-us/windows/uwp/packaging/app-capability-declarations#general-use-capabilities
+    cap("internetClient",                   Foundation, false,  Some(1)),
+    cap("internetClientServer",             Foundation, false,  Some(2)),
+    cap("privateNetworkClientServer",       Foundation, false,  Some(3)),
+    cap("picturesLibrary",                  Uap,        false,  Some(4)),
+    cap("videosLibrary",                    Uap,        false,  Some(5)),
+    cap("musicLibrary",                     Uap,        false,  Some(6)),
+    cap("removableStorage",                 Uap,        false,  Some(10)),
+    cap("appointments",                     Uap,        false,  Some(11)),
+    cap("contacts",                         Uap,        false,  Some(12)),
+    cap("userAccountInformation",           Uap,        false,  None),
+    cap("voipCall",                         Uap,        false,  None),
+    cap("objects3D",                        Uap,        false,  None),
+    cap("phoneCall",                        Uap,        false,  None),
+    cap("spatialPerception",                Uap2,       false,  None),
+    cap("backgroundMediaPlayback",          Uap3,       false,  None),
+    cap("remoteSystem",                     Uap3,       false,  None),
+    cap("userNotificationListener",         Uap3,       false,  None),
+    cap("userDataTasks",                    Uap4,       false,  None),
+    cap("graphicsCapture",                  Uap6,       false,  None),
+    cap("globalMediaControl",               Uap7,       false,  None),
+    cap("graphicsCaptureWithoutBorder",     Uap11,      false,  None),
+    cap("graphicsCaptureProgrammatic",      Uap11,      false,  None),
+
+    // https://docs.microsoft.com/en-us/windows/uwp/packaging/app-capability-declarations#special-and-restricted-capabilities
+    cap("documentsLibrary",                 Uap,        true,   Some(7)),
+    cap("enterpriseAuthentication",         Uap,        true,   Some(8)),
+    cap("sharedUserCertificates",           Uap,        true,   Some(9)),
+    cap("chat",                             Uap,        true,   None),
+    cap("blockedChatMessages",              Uap,        true,   None),
+    cap("allowElevation",                   Rescap,     true,   None),
+    cap("appDiagnostics",                   Rescap,     true,   None),
+    cap("appLicensing",                     Rescap,     true,   None),
+    cap("broadFileSystemAccess",            Rescap,     true,   None),
+    cap("confirmAppClose",                  Rescap,     true,   None),
+    cap("extendedBackgroundTaskTime",       Rescap,     true,   None),
+    cap("extendedExecutionUnconstrained",   Rescap,     true,   None),
+    cap("inputInjectionBrokered",           Rescap,     true,   None),
+    cap("localSystemServices",              Rescap,     true,   None),
+    cap("packageManagement",                Rescap,     true,   None),
+    cap("packagedServices",                 Rescap,     true,   None),
+    cap("packageQuery",                     Rescap,     true,   None),
+    cap("runFullTrust",                     Rescap,     true,   None),
+    cap("unvirtualizedResources",           Rescap,     true,   None),
+
+    // https://docs.microsoft.com/en-us/windows/uwp/packaging/app-capability-declarations#device-capabilities
+    cap("bluetooth",                        Device,     false,  None),
+    cap("location",                         Device,     false,  None),
+    cap("microphone",                       Device,     false,  None),
+    cap("proximity",                        Device,     false,  None),
+    cap("radios",                           Device,     false,  None),
+    cap("webcam",                           Device,     false,  None),
+];
+
+impl Capability {
+    /// All capabilities known to this crate
+    pub fn all() -> &'static [Capability] { CATALOG }
+
+    /// Find a known capability by name (case insensitive, e.g. `internetClient`)
+    pub fn find(name: &str) -> Option<&'static Capability> {
+        let name = name.encode_utf16().collect::<Vec<u16>>();
+        CATALOG.iter().find(|c| ordinal::eq_ignore_case(&c.name.encode_utf16().collect::<Vec<u16>>(), &name))
+    }
+
+    /// Find the known capability a [Sid] belongs to - either a well known `S-1-15-3-N` SID, or a derived `S-1-15-3-1024-...` SID
+    pub fn from_sid(sid: &Sid) -> Option<&'static Capability> {
+        CATALOG.iter().find(|c| c.sid() == *sid)
+    }
+
+    /// The capability name (e.g. `internetClient`)
+    pub fn name(&self) -> &'static str { self.name }
+
+    /// The XML namespace the capability is declared in
+    pub fn namespace(&self) -> CapabilityNamespace { self.namespace }
+
+    /// Is this a [restricted or special use](https://docs.microsoft.com/en-us/windows/uwp/packaging/app-capability-declarations#special-and-restricted-capabilities) capability?
+    pub fn is_restricted(&self) -> bool { self.restricted }
+
+    /// The capability's SID: well known `S-1-15-3-N` SIDs for legacy capabilities (e.g. `internetClient` → `S-1-15-3-1`), a derived `S-1-15-3-1024-...` SID otherwise
+    pub fn sid(&self) -> Sid {
+        match self.well_known_rid {
+            Some(rid)   => Sid::new(Sid::APP_PACKAGE_AUTHORITY, vec![3, rid]).expect("well known capability SID"),
+            None        => derived_capability_sid(self.name),
+        }
+    }
+
+    /// The capability's derived `S-1-15-3-1024-...` SID, even if it also has a well known SID
+    pub fn derived_sid(&self) -> Sid { derived_capability_sid(self.name) }
+}
+
+impl Display for Capability {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self.namespace.prefix() {
+            Some(prefix)    => write!(fmt, "{}:{}", prefix, self.name),
+            None            => fmt.write_str(self.name),
+        }
+    }
+}
+
+/// Derive the `S-1-15-3-1024-...` capability SID of an arbitrary capability name, the same way as [DeriveCapabilitySidsFromName]:
+/// the 8 little endian `u32`s of the SHA-256 of the uppercased (UTF-16LE) capability name.
+///
+/// Works for capabilities that aren't in the [Capability] catalog (e.g. custom or newer capabilities.)
+///
+/// [DeriveCapabilitySidsFromName]: https://docs.microsoft.com/en-us/windows/win32/api/securitybaseapi/nf-securitybaseapi-derivecapabilitysidsfromname
+pub fn derived_capability_sid(name: &str) -> Sid {
+    crate::sid::app_package_sid_from_hash(&[3, 1024], name.encode_utf16().map(ordinal::upcase), 8)
+}
+
+
+/// Decode a package's `CapabilitySids` `REG_BINARY` blob: the packed binary forms of each capability [Sid], back to back.
+///
+/// If `expected_count` (the package's `CapabilityCount`) is provided, the number of decoded [Sid]s must match it.
+/// Use [Capability::from_sid] to map the results back to capability names where known.
+///
+/// The back to back layout is an unverified assumption - it matches the documented packed [Sid] format,
+/// but hasn't been checked against a real `CapabilitySids` value exported from a machine.
+///
+/// ### Examples
+///
+/// ```rust
+/// # use appx::*;
+/// let blob = [
+///     1, 2, 0, 0, 0, 0, 0, 15,    3, 0, 0, 0,     1, 0, 0, 0, // S-1-15-3-1
+///     1, 2, 0, 0, 0, 0, 0, 15,    3, 0, 0, 0,     3, 0, 0, 0, // S-1-15-3-3
+/// ];
+/// let sids = parse_capability_sids(&blob, Some(2)).unwrap();
+/// let names = sids.iter().map(|sid| Capability::from_sid(sid).map(|c| c.name())).collect::<Vec<_>>();
+/// assert_eq!(names, [Some("internetClient"), Some("privateNetworkClientServer")]);
+/// ```
+///
+/// ### Corresponds to
+///
+/// `HKCR\Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages\...\CapabilitySids`
+pub fn parse_capability_sids(blob: &[u8], expected_count: Option<u32>) -> Result<Vec<Sid>, CapabilitySidsError> {
+    let mut sids = Vec::new();
+    let mut offset = 0;
+    while offset < blob.len() {
+        let (sid, len) = Sid::from_bytes_prefix(&blob[offset..]).map_err(|_| CapabilitySidsError::MalformedSid { offset })?;
+        sids.push(sid);
+        offset += len;
+    }
+    if let Some(expected) = expected_count {
+        if sids.len() != expected as usize { return Err(CapabilitySidsError::Count { expected, found: sids.len() }); }
+    }
+    Ok(sids)
+}
+
+
+
+/// A `CapabilitySids` blob failed to [parse](parse_capability_sids)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CapabilitySidsError {
+    /// The [Sid] starting at byte `offset` was malformed or truncated
+    MalformedSid { offset: usize },
+    /// The blob didn't contain `CapabilityCount` [Sid]s
+    Count { expected: u32, found: usize },
+}
+
+impl Error   for CapabilitySidsError {}
+impl Display for CapabilitySidsError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            CapabilitySidsError::MalformedSid { offset }    => write!(fmt, "CapabilitySids contains a malformed SID at byte {}", offset),
+            CapabilitySidsError::Count { expected, found }  => write!(fmt, "CapabilitySids contains {} SIDs, but CapabilityCount is {}", found, expected),
+        }
+    }
+}
+
+
+#[test] fn test_capability() {
+    assert_eq!(Capability::find("internetClient").unwrap().sid().to_string(),  "S-1-15-3-1");
+    assert_eq!(Capability::find("INTERNETCLIENT").unwrap().name(),              "internetClient");
+    assert_eq!(Capability::find("contacts").unwrap().sid().to_string(),         "S-1-15-3-12");
+    assert_eq!(Capability::find("picturesLibrary").unwrap().to_string(),        "uap:picturesLibrary");
+    assert_eq!(Capability::find("webcam").unwrap().to_string(),                 "webcam");
+    assert_eq!(Capability::find("nonexistent"), None);
+
+    for cap in Capability::all() {
+        assert_eq!(Capability::from_sid(&cap.sid()),    Some(cap), "{}", cap);
+        assert_eq!(Capability::find(cap.name()),        Some(cap), "{}", cap);
+        if cap.namespace() == CapabilityNamespace::Rescap { assert!(cap.is_restricted()); }
+        assert_eq!(cap.derived_sid().sub_authorities().len(), 10);
+        assert_eq!(&cap.derived_sid().sub_authorities()[..2], &[3, 1024]);
+    }
+
+    // Known answer: the registryRead SID granted to LPAC processes, as listed by e.g. `icacls` on registry ACLs
+    assert_eq!(derived_capability_sid("registryRead").to_string(), "S-1-15-3-1024-1065365936-1281604716-3511738428-1654721687-432734479-3232135806-4053264122-3456934681");
+
+    // Derivation is case insensitive, and works for names outside the catalog
+    assert_eq!(derived_capability_sid("runFullTrust"), derived_capability_sid("RUNFULLTRUST"));
+    assert_ne!(derived_capability_sid("runFullTrust"), derived_capability_sid("runFullTrust2"));
+    assert_eq!(Capability::from_sid(&derived_capability_sid("someCustomCapability")), None);
+    assert_eq!(Capability::from_sid(&"S-1-15-3-4".parse().unwrap()).map(|c| c.name()), Some("picturesLibrary"));
+}
+
+#[test] fn test_parse_capability_sids() {
+    // internetClient, privateNetworkClientServer, runFullTrust
+    let run_full_trust = Capability::find("runFullTrust").unwrap().sid();
+    let mut blob = vec![
+        1, 2, 0, 0, 0, 0, 0, 15,    3, 0, 0, 0,     1, 0, 0, 0,
+        1, 2, 0, 0, 0, 0, 0, 15,    3, 0, 0, 0,     3, 0, 0, 0,
+    ];
+    blob.extend(run_full_trust.to_bytes());
+
+    let sids = parse_capability_sids(&blob, Some(3)).unwrap();
+    assert_eq!(sids.len(), 3);
+    assert_eq!(sids[0].to_string(), "S-1-15-3-1");
+    assert_eq!(sids[1].to_string(), "S-1-15-3-3");
+    assert_eq!(sids[2], run_full_trust);
+    assert_eq!(Capability::from_sid(&sids[2]).map(|c| c.name()), Some("runFullTrust"));
+    assert_eq!(parse_capability_sids(&blob, None).unwrap().len(), 3);
+
+    assert_eq!(parse_capability_sids(&[], Some(0)),                 Ok(Vec::new()));
+    assert_eq!(parse_capability_sids(&blob, Some(2)),               Err(CapabilitySidsError::Count { expected: 2, found: 3 }));
+    assert_eq!(parse_capability_sids(&blob[..blob.len()-1], None),  Err(CapabilitySidsError::MalformedSid { offset: 32 }));
+    assert_eq!(parse_capability_sids(&blob[1..], None),             Err(CapabilitySidsError::MalformedSid { offset: 0 }));
+}
@@ -0,0 +1,275 @@
+//! Launching external tools (e.g. `powershell`) via a swappable [CommandRunner].
+//!
+//! | Runner            | Runs |
+//! | ----------------- | ---- |
+//! | [SystemRunner]    | Real processes, via [std::process::Command]
+//! | [MockRunner]      | Nothing - records every [CommandLine] and replays scripted [CommandOutput]s or errors, for tests
+
+use std::collections::VecDeque;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+
+
+/// A program, its arguments, extra environment variables, and how long to let it run.
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::command::CommandLine;
+/// let cmd = CommandLine::new("powershell").args(["-NoProfile", "-Command", "Get-AppxPackage"]).env("NO_COLOR", "1");
+/// assert_eq!(cmd.to_string(), "powershell -NoProfile -Command Get-AppxPackage");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandLine {
+    program:    OsString,
+    args:       Vec<OsString>,
+    envs:       Vec<(OsString, OsString)>,
+    timeout:    Option<Duration>,
+}
+
+impl CommandLine {
+    /// Run `program` with no arguments, the inherited environment, and no timeout
+    pub fn new(program: impl Into<OsString>) -> Self { Self { program: program.into(), args: Vec::new(), envs: Vec::new(), timeout: None } }
+
+    /// Append an argument
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self { self.args.push(arg.into()); self }
+
+    /// Append several arguments
+    pub fn args<A: Into<OsString>>(mut self, args: impl IntoIterator<Item = A>) -> Self { self.args.extend(args.into_iter().map(Into::into)); self }
+
+    /// Set an environment variable (in addition to the inherited environment)
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self { self.envs.push((key.into(), value.into())); self }
+
+    /// Kill the program and fail with [io::ErrorKind::TimedOut] if it runs longer than `timeout`
+    pub fn timeout(self, timeout: impl Into<Option<Duration>>) -> Self { Self { timeout: timeout.into(), ..self } }
+
+    /// The program to run
+    pub fn get_program(&self) -> &OsStr { &self.program }
+
+    /// The arguments to pass to the program
+    pub fn get_args(&self) -> &[OsString] { &self.args }
+
+    /// The environment variables set in addition to the inherited environment
+    pub fn get_envs(&self) -> &[(OsString, OsString)] { &self.envs }
+
+    /// How long to let the program run, if limited
+    pub fn get_timeout(&self) -> Option<Duration> { self.timeout }
+}
+
+/// Space separated, with arguments containing whitespace or `"` quoted (for logging - not for passing to a shell)
+impl Display for CommandLine {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.program.to_string_lossy())?;
+        for arg in self.args.iter() {
+            let arg = arg.to_string_lossy();
+            if arg.is_empty() || arg.contains(|ch: char| ch.is_whitespace() || ch == '"') {
+                write!(fmt, " \"{}\"", arg.replace('"', "\\\""))?;
+            } else {
+                write!(fmt, " {}", arg)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+
+/// The exit code and captured output of a finished [CommandLine]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    code:   Option<i32>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl CommandOutput {
+    /// An exit code (or [None] if terminated by a signal) and captured output
+    pub fn new(code: Option<i32>, stdout: impl Into<Vec<u8>>, stderr: impl Into<Vec<u8>>) -> Self { Self { code, stdout: stdout.into(), stderr: stderr.into() } }
+
+    /// Exit code `0` with `stdout`
+    pub fn success(stdout: impl Into<Vec<u8>>) -> Self { Self::new(Some(0), stdout, Vec::new()) }
+
+    /// Exit code `code` with `stderr`
+    pub fn failure(code: i32, stderr: impl Into<Vec<u8>>) -> Self { Self::new(Some(code), Vec::new(), stderr) }
+
+    /// The exit code, or [None] if the program was terminated by a signal
+    pub fn code(&self) -> Option<i32> { self.code }
+
+    /// The exit code was `0`
+    pub fn is_success(&self) -> bool { self.code == Some(0) }
+
+    /// Everything written to stdout
+    pub fn stdout(&self) -> &[u8] { &self.stdout }
+
+    /// Everything written to stderr
+    pub fn stderr(&self) -> &[u8] { &self.stderr }
+}
+
+impl From<std::process::Output> for CommandOutput {
+    fn from(output: std::process::Output) -> Self { Self::new(output.status.code(), output.stdout, output.stderr) }
+}
+
+
+
+/// Something that can run a [CommandLine] to completion
+pub trait CommandRunner {
+    /// Run `command` with stdin closed, capturing stdout and stderr.
+    /// A program that runs but exits with a nonzero code is still `Ok` - check [CommandOutput::is_success].
+    fn run(&self, command: &CommandLine) -> io::Result<CommandOutput>;
+}
+
+/// Runs real processes via [std::process::Command]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, command: &CommandLine) -> io::Result<CommandOutput> {
+        let mut child = Command::new(&command.program)
+            .args(&command.args)
+            .envs(command.envs.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let timeout = match command.timeout {
+            None            => return child.wait_with_output().map(CommandOutput::from),
+            Some(timeout)   => timeout,
+        };
+
+        // read both pipes concurrently so a chatty child can't block on a full pipe while we poll for its exit
+        let stdout = child.stdout.take().map(read_to_end_thread);
+        let stderr = child.stderr.take().map(read_to_end_thread);
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? { break status; }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(io::Error::new(io::ErrorKind::TimedOut, format!("`{}` timed out after {:?}", command, timeout)));
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        Ok(CommandOutput::new(status.code(), join(stdout)?, join(stderr)?))
+    }
+}
+
+fn read_to_end_thread(mut pipe: impl Read + Send + 'static) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || { let mut buf = Vec::new(); pipe.read_to_end(&mut buf).map(|_| buf) })
+}
+
+#[allow(clippy::io_other_error)] // io::Error::other requires rust 1.74
+fn join(reader: Option<JoinHandle<io::Result<Vec<u8>>>>) -> io::Result<Vec<u8>> {
+    match reader {
+        Some(reader)    => reader.join().unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::Other, "pipe reader thread panicked"))),
+        None            => Ok(Vec::new()),
+    }
+}
+
+
+
+/// Runs nothing: records every [CommandLine] and replays queued [CommandOutput]s (or errors) in order.
+/// Clones share the same recording and queue.
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::command::*;
+/// let mock = MockRunner::new();
+/// mock.reply(CommandOutput::success("[]"));
+/// mock.reply_err(std::io::ErrorKind::NotFound, "program not found");
+///
+/// let cmd = CommandLine::new("pwsh").arg("-NoProfile");
+/// assert_eq!(mock.run(&cmd).unwrap().stdout(), b"[]");
+/// assert_eq!(mock.run(&cmd).unwrap_err().kind(), std::io::ErrorKind::NotFound);
+/// assert_eq!(mock.commands(), [cmd.clone(), cmd]);
+/// ```
+#[derive(Clone, Default)]
+pub struct MockRunner(Arc<Mutex<MockState>>);
+
+#[derive(Default)]
+struct MockState {
+    replies:    VecDeque<Result<CommandOutput, (io::ErrorKind, String)>>,
+    commands:   Vec<CommandLine>,
+}
+
+impl MockRunner {
+    /// A runner with nothing queued or recorded
+    pub fn new() -> Self { Self::default() }
+
+    /// Queue `output` as the result of the next unanswered [run](CommandRunner::run)
+    pub fn reply(&self, output: CommandOutput) { self.state().replies.push_back(Ok(output)); }
+
+    /// Queue an [io::Error] (e.g. a failure to launch) as the result of the next unanswered [run](CommandRunner::run)
+    pub fn reply_err(&self, kind: io::ErrorKind, message: impl Into<String>) { self.state().replies.push_back(Err((kind, message.into()))); }
+
+    /// Every [CommandLine] run so far, in order
+    pub fn commands(&self) -> Vec<CommandLine> { self.state().commands.clone() }
+
+    /// How many queued replies have yet to be used
+    pub fn pending(&self) -> usize { self.state().replies.len() }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> { self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) }
+}
+
+impl Debug for MockRunner {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let state = self.state();
+        fmt.debug_struct("MockRunner").field("pending", &state.replies.len()).field("commands", &state.commands).finish()
+    }
+}
+
+impl CommandRunner for MockRunner {
+    #[allow(clippy::io_other_error)] // io::Error::other requires rust 1.74
+    fn run(&self, command: &CommandLine) -> io::Result<CommandOutput> {
+        let mut state = self.state();
+        state.commands.push(command.clone());
+        match state.replies.pop_front() {
+            Some(Ok(output))            => Ok(output),
+            Some(Err((kind, message)))  => Err(io::Error::new(kind, message)),
+            None                        => Err(io::Error::new(io::ErrorKind::Other, format!("MockRunner: no reply queued for `{}`", command))),
+        }
+    }
+}
+
+
+
+#[test] fn test_command() {
+    let cmd = CommandLine::new("pwsh").args(["-Command", "Write-Output 'a \"b\"'", ""]).env("A", "1").timeout(Duration::from_secs(1));
+    assert_eq!(cmd.get_program(),   "pwsh");
+    assert_eq!(cmd.get_args(),      ["-Command", "Write-Output 'a \"b\"'", ""]);
+    assert_eq!(cmd.get_envs(),      [(OsString::from("A"), OsString::from("1"))]);
+    assert_eq!(cmd.get_timeout(),   Some(Duration::from_secs(1)));
+    assert_eq!(cmd.to_string(),     r#"pwsh -Command "Write-Output 'a \"b\"'" """#);
+
+    let mock = MockRunner::new();
+    mock.reply(CommandOutput::failure(1, "nope"));
+    assert_eq!(mock.pending(), 1);
+    assert_eq!(mock.run(&cmd).unwrap(), CommandOutput::new(Some(1), "", "nope"));
+    assert_eq!(mock.run(&cmd).unwrap_err().kind(), io::ErrorKind::Other); // nothing queued
+    assert_eq!(mock.pending(), 0);
+    assert_eq!(mock.clone().commands(), [cmd.clone(), cmd]);
+
+    if cfg!(unix) {
+        let sh = |script: &str| CommandLine::new("sh").args(["-c", script]);
+        let out = SystemRunner.run(&sh("printf \"$GREETING\"; printf oops >&2; exit 3").env("GREETING", "hello")).unwrap();
+        assert_eq!(out, CommandOutput::new(Some(3), "hello", "oops"));
+        assert!(!out.is_success());
+
+        let out = SystemRunner.run(&sh("echo done").timeout(Duration::from_secs(60))).unwrap();
+        assert!(out.is_success());
+        assert_eq!(out.stdout(), b"done\n");
+
+        let start = Instant::now();
+        assert_eq!(SystemRunner.run(&sh("exec sleep 10").timeout(Duration::from_millis(50))).unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+    assert_eq!(SystemRunner.run(&CommandLine::new("appx-test-missing-program")).unwrap_err().kind(), io::ErrorKind::NotFound);
+}
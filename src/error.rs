@@ -0,0 +1,394 @@
+use crate::powershell::PowerShellError;
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+
+
+/// e.g. `ERROR_PACKAGES_IN_USE` (`0x80073D02`) -
+/// A decoded deployment failure, with a remediation hint and whether retrying might help.
+///
+/// Covers the deployment HRESULTs `0x80073CF0..=0x80073D2F`, plus the signature and trust HRESULTs `Add-AppxPackage` commonly fails with.
+/// Anything else is [Other](Self::Other).
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::AppxError;
+/// let err = AppxError::from_hresult(0x80073D02);
+/// assert_eq!(err, AppxError::PackagesInUse);
+/// assert_eq!(err.name(), Some("ERROR_PACKAGES_IN_USE"));
+/// assert!(err.is_retriable());
+/// println!("{}: {}", err, err.remediation());
+///
+/// let record = "Add-AppxPackage : Deployment failed with HRESULT: 0x800B0109, A certificate chain processed, but terminated in a root certificate which is not trusted by the trust provider.";
+/// let err = AppxError::parse(record).unwrap();
+/// assert_eq!(err, AppxError::CertUntrustedRoot);
+/// assert!(err.is_signature_error());
+/// assert!(!err.is_retriable());
+/// ```
+///
+/// ### Corresponds to
+///
+/// [Troubleshooting packaging, deployment, and query of Windows apps](https://docs.microsoft.com/en-us/windows/win32/appxpkg/troubleshooting)<br>
+/// `winerror.h`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AppxError {
+    /// `0x80073CF0` `ERROR_INSTALL_OPEN_PACKAGE_FAILED`
+    InstallOpenPackageFailed,
+    /// `0x80073CF1` `ERROR_INSTALL_PACKAGE_NOT_FOUND`
+    InstallPackageNotFound,
+    /// `0x80073CF2` `ERROR_INSTALL_INVALID_PACKAGE`
+    InstallInvalidPackage,
+    /// `0x80073CF3` `ERROR_INSTALL_RESOLVE_DEPENDENCY_FAILED`
+    InstallResolveDependencyFailed,
+    /// `0x80073CF4` `ERROR_INSTALL_OUT_OF_DISK_SPACE`
+    InstallOutOfDiskSpace,
+    /// `0x80073CF5` `ERROR_INSTALL_NETWORK_FAILURE`
+    InstallNetworkFailure,
+    /// `0x80073CF6` `ERROR_INSTALL_REGISTRATION_FAILURE`
+    InstallRegistrationFailure,
+    /// `0x80073CF7` `ERROR_INSTALL_DEREGISTRATION_FAILURE`
+    InstallDeregistrationFailure,
+    /// `0x80073CF8` `ERROR_INSTALL_CANCEL`
+    InstallCancel,
+    /// `0x80073CF9` `ERROR_INSTALL_FAILED`
+    InstallFailed,
+    /// `0x80073CFA` `ERROR_REMOVE_FAILED`
+    RemoveFailed,
+    /// `0x80073CFB` `ERROR_PACKAGE_ALREADY_EXISTS`
+    PackageAlreadyExists,
+    /// `0x80073CFC` `ERROR_NEEDS_REMEDIATION`
+    NeedsRemediation,
+    /// `0x80073CFD` `ERROR_INSTALL_PREREQUISITE_FAILED`
+    InstallPrerequisiteFailed,
+    /// `0x80073CFE` `ERROR_PACKAGE_REPOSITORY_CORRUPTED`
+    PackageRepositoryCorrupted,
+    /// `0x80073CFF` `ERROR_INSTALL_POLICY_FAILURE`
+    InstallPolicyFailure,
+    /// `0x80073D00` `ERROR_PACKAGE_UPDATING`
+    PackageUpdating,
+    /// `0x80073D01` `ERROR_DEPLOYMENT_BLOCKED_BY_POLICY`
+    DeploymentBlockedByPolicy,
+    /// `0x80073D02` `ERROR_PACKAGES_IN_USE`
+    PackagesInUse,
+    /// `0x80073D03` `ERROR_RECOVERY_FILE_CORRUPT`
+    RecoveryFileCorrupt,
+    /// `0x80073D04` `ERROR_INVALID_STAGED_SIGNATURE`
+    InvalidStagedSignature,
+    /// `0x80073D05` `ERROR_DELETING_EXISTING_APPLICATIONDATA_STORE_FAILED`
+    DeletingExistingApplicationdataStoreFailed,
+    /// `0x80073D06` `ERROR_INSTALL_PACKAGE_DOWNGRADE`
+    InstallPackageDowngrade,
+    /// `0x80073D07` `ERROR_SYSTEM_NEEDS_REMEDIATION`
+    SystemNeedsRemediation,
+    /// `0x80073D08` `ERROR_APPX_INTEGRITY_FAILURE_CLR_NGEN`
+    AppxIntegrityFailureClrNgen,
+    /// `0x80073D09` `ERROR_RESILIENCY_FILE_CORRUPT`
+    ResiliencyFileCorrupt,
+    /// `0x80073D0A` `ERROR_INSTALL_FIREWALL_SERVICE_NOT_RUNNING`
+    InstallFirewallServiceNotRunning,
+    /// `0x80073D0B` `ERROR_PACKAGE_MOVE_FAILED`
+    PackageMoveFailed,
+    /// `0x80073D0C` `ERROR_INSTALL_VOLUME_NOT_EMPTY`
+    InstallVolumeNotEmpty,
+    /// `0x80073D0D` `ERROR_INSTALL_VOLUME_OFFLINE`
+    InstallVolumeOffline,
+    /// `0x80073D0E` `ERROR_INSTALL_VOLUME_CORRUPT`
+    InstallVolumeCorrupt,
+    /// `0x80073D0F` `ERROR_NEEDS_REGISTRATION`
+    NeedsRegistration,
+    /// `0x80073D10` `ERROR_INSTALL_WRONG_PROCESSOR_ARCHITECTURE`
+    InstallWrongProcessorArchitecture,
+    /// `0x80073D11` `ERROR_DEV_SIDELOAD_LIMIT_EXCEEDED`
+    DevSideloadLimitExceeded,
+    /// `0x80073D12` `ERROR_INSTALL_OPTIONAL_PACKAGE_REQUIRES_MAIN_PACKAGE`
+    InstallOptionalPackageRequiresMainPackage,
+    /// `0x80073D13` `ERROR_PACKAGE_NOT_SUPPORTED_ON_FILESYSTEM`
+    PackageNotSupportedOnFilesystem,
+    /// `0x80073D14` `ERROR_PACKAGE_MOVE_BLOCKED_BY_STREAMING`
+    PackageMoveBlockedByStreaming,
+    /// `0x80073D15` `ERROR_INSTALL_OPTIONAL_PACKAGE_APPLICATIONID_NOT_UNIQUE`
+    InstallOptionalPackageApplicationidNotUnique,
+    /// `0x80073D16` `ERROR_PACKAGE_STAGING_ONHOLD`
+    PackageStagingOnhold,
+    /// `0x80073D17` `ERROR_INSTALL_INVALID_RELATED_SET_UPDATE`
+    InstallInvalidRelatedSetUpdate,
+    /// `0x80073D18` `ERROR_INSTALL_OPTIONAL_PACKAGE_REQUIRES_MAIN_PACKAGE_FULLTRUST_CAPABILITY`
+    InstallOptionalPackageRequiresMainPackageFulltrustCapability,
+    /// `0x80073D19` `ERROR_DEPLOYMENT_BLOCKED_BY_USER_LOG_OFF`
+    DeploymentBlockedByUserLogOff,
+    /// `0x80073D1A` `ERROR_PROVISION_OPTIONAL_PACKAGE_REQUIRES_MAIN_PACKAGE_PROVISIONED`
+    ProvisionOptionalPackageRequiresMainPackageProvisioned,
+    /// `0x80073D1B` `ERROR_PACKAGES_REPUTATION_CHECK_FAILED`
+    PackagesReputationCheckFailed,
+    /// `0x80073D1C` `ERROR_PACKAGES_REPUTATION_CHECK_TIMEDOUT`
+    PackagesReputationCheckTimedout,
+    /// `0x80073D1D` `ERROR_DEPLOYMENT_OPTION_NOT_SUPPORTED`
+    DeploymentOptionNotSupported,
+    /// `0x80073D1E` `ERROR_APPINSTALLER_ACTIVATION_BLOCKED`
+    AppinstallerActivationBlocked,
+    /// `0x80073D1F` `ERROR_REGISTRATION_FROM_REMOTE_DRIVE_NOT_SUPPORTED`
+    RegistrationFromRemoteDriveNotSupported,
+    /// `0x80073D20` `ERROR_APPX_RAW_DATA_WRITE_FAILED`
+    AppxRawDataWriteFailed,
+    /// `0x80073D21` `ERROR_DEPLOYMENT_BLOCKED_BY_VOLUME_POLICY_PACKAGE`
+    DeploymentBlockedByVolumePolicyPackage,
+    /// `0x80073D22` `ERROR_DEPLOYMENT_BLOCKED_BY_VOLUME_POLICY_MACHINE`
+    DeploymentBlockedByVolumePolicyMachine,
+    /// `0x80073D23` `ERROR_DEPLOYMENT_BLOCKED_BY_PROFILE_POLICY`
+    DeploymentBlockedByProfilePolicy,
+    /// `0x80073D24` `ERROR_DEPLOYMENT_FAILED_CONFLICTING_MUTABLE_PACKAGE_DIRECTORY`
+    DeploymentFailedConflictingMutablePackageDirectory,
+    /// `0x80073D25` `ERROR_SINGLETON_RESOURCE_INSTALLED_IN_ACTIVE_USER`
+    SingletonResourceInstalledInActiveUser,
+    /// `0x80073D26` `ERROR_DIFFERENT_VERSION_OF_PACKAGED_SERVICE_INSTALLED`
+    DifferentVersionOfPackagedServiceInstalled,
+    /// `0x80073D27` `ERROR_SERVICE_EXISTS_AS_NON_PACKAGED_SERVICE`
+    ServiceExistsAsNonPackagedService,
+    /// `0x80073D28` `ERROR_PACKAGED_SERVICE_REQUIRES_ADMIN_PRIVILEGES`
+    PackagedServiceRequiresAdminPrivileges,
+    /// `0x80073D29` `ERROR_REDIRECTION_TO_DEFAULT_ACCOUNT_NOT_ALLOWED`
+    RedirectionToDefaultAccountNotAllowed,
+    /// `0x80073D2A` `ERROR_PACKAGE_LACKS_CAPABILITY_TO_DEPLOY_ON_HOST`
+    PackageLacksCapabilityToDeployOnHost,
+    /// `0x80073D2B` `ERROR_UNSIGNED_PACKAGE_INVALID_CONTENT`
+    UnsignedPackageInvalidContent,
+    /// `0x80073D2C` `ERROR_UNSIGNED_PACKAGE_INVALID_PUBLISHER_NAMESPACE`
+    UnsignedPackageInvalidPublisherNamespace,
+    /// `0x80073D2D` `ERROR_SIGNED_PACKAGE_INVALID_PUBLISHER_NAMESPACE`
+    SignedPackageInvalidPublisherNamespace,
+    /// `0x80073D2E` `ERROR_PACKAGE_EXTERNAL_LOCATION_NOT_ALLOWED`
+    PackageExternalLocationNotAllowed,
+    /// `0x80073D2F` `ERROR_INSTALL_FULLTRUST_HOSTRUNTIME_REQUIRES_MAIN_PACKAGE_FULLTRUST_CAPABILITY`
+    InstallFulltrustHostruntimeRequiresMainPackageFulltrustCapability,
+
+    // signature and trust
+
+    /// `0x800B0100` `TRUST_E_NOSIGNATURE`
+    TrustNoSignature,
+    /// `0x800B0101` `CERT_E_EXPIRED`
+    CertExpired,
+    /// `0x800B0109` `CERT_E_UNTRUSTEDROOT`
+    CertUntrustedRoot,
+    /// `0x800B010A` `CERT_E_CHAINING`
+    CertChaining,
+    /// `0x800B010C` `CERT_E_REVOKED`
+    CertRevoked,
+    /// `0x80096004` `TRUST_E_CERT_SIGNATURE`
+    TrustCertSignature,
+    /// `0x80096010` `TRUST_E_BAD_DIGEST`
+    TrustBadDigest,
+    /// `0x80080209` `APPX_E_INVALID_SIP_CLIENT_DATA`
+    AppxInvalidSipClientData,
+
+    /// Any other HRESULT
+    Other(u32),
+}
+
+struct Entry {
+    error:          AppxError,
+    hresult:        u32,
+    name:           &'static str,
+    retriable:      bool,
+    remediation:    &'static str,
+}
+
+const fn e(error: AppxError, hresult: u32, name: &'static str, retriable: bool, remediation: &'static str) -> Entry {
+    Entry { error, hresult, name, retriable, remediation }
+}
+
+const CATALOG : &[Entry] = { use AppxError::*; &[
+    // https://docs.microsoft.com/en-us/windows/win32/appxpkg/troubleshooting#deployment-errors
+    e(InstallOpenPackageFailed,                                          0x80073CF0, "ERROR_INSTALL_OPEN_PACKAGE_FAILED",                                              false, "The package file couldn't be opened - check that it exists, is readable, and isn't locked by another process."),
+    e(InstallPackageNotFound,                                            0x80073CF1, "ERROR_INSTALL_PACKAGE_NOT_FOUND",                                                false, "The package wasn't found - check the package full name against `Get-AppxPackage`."),
+    e(InstallInvalidPackage,                                             0x80073CF2, "ERROR_INSTALL_INVALID_PACKAGE",                                                  false, "The package data is invalid - rebuild the package and check its manifest."),
+    e(InstallResolveDependencyFailed,                                    0x80073CF3, "ERROR_INSTALL_RESOLVE_DEPENDENCY_FAILED",                                        false, "A dependency or conflict check failed - install the required framework packages first, or remove the conflicting package."),
+    e(InstallOutOfDiskSpace,                                             0x80073CF4, "ERROR_INSTALL_OUT_OF_DISK_SPACE",                                                false, "The target volume is out of space - free up disk space and try again."),
+    e(InstallNetworkFailure,                                             0x80073CF5, "ERROR_INSTALL_NETWORK_FAILURE",                                                  true,  "The package couldn't be downloaded - check network connectivity and try again."),
+    e(InstallRegistrationFailure,                                        0x80073CF6, "ERROR_INSTALL_REGISTRATION_FAILURE",                                             false, "The package couldn't be registered - check the event log for the activity ID."),
+    e(InstallDeregistrationFailure,                                      0x80073CF7, "ERROR_INSTALL_DEREGISTRATION_FAILURE",                                           false, "The package couldn't be unregistered - check the event log for the activity ID."),
+    e(InstallCancel,                                                     0x80073CF8, "ERROR_INSTALL_CANCEL",                                                           true,  "The deployment was cancelled - try again."),
+    e(InstallFailed,                                                     0x80073CF9, "ERROR_INSTALL_FAILED",                                                           false, "The install failed - run `Get-AppPackageLog -ActivityID ...` for details."),
+    e(RemoveFailed,                                                      0x80073CFA, "ERROR_REMOVE_FAILED",                                                            false, "The removal failed - run `Get-AppPackageLog -ActivityID ...` for details."),
+    e(PackageAlreadyExists,                                              0x80073CFB, "ERROR_PACKAGE_ALREADY_EXISTS",                                                   false, "A different package with the same identity is already installed - remove it first, or bump the package version."),
+    e(NeedsRemediation,                                                  0x80073CFC, "ERROR_NEEDS_REMEDIATION",                                                        false, "The app needs to be repaired - reinstall it."),
+    e(InstallPrerequisiteFailed,                                         0x80073CFD, "ERROR_INSTALL_PREREQUISITE_FAILED",                                              false, "A prerequisite wasn't met - check the package's OS version and architecture requirements."),
+    e(PackageRepositoryCorrupted,                                        0x80073CFE, "ERROR_PACKAGE_REPOSITORY_CORRUPTED",                                             false, "The package repository is corrupted - see Microsoft's guidance on repairing the AppRepository."),
+    e(InstallPolicyFailure,                                              0x80073CFF, "ERROR_INSTALL_POLICY_FAILURE",                                                   false, "Policy blocks this install - enable sideloading or developer mode, or sign the package with a trusted certificate."),
+    e(PackageUpdating,                                                   0x80073D00, "ERROR_PACKAGE_UPDATING",                                                         true,  "The package is being updated - wait for the update to finish and try again."),
+    e(DeploymentBlockedByPolicy,                                         0x80073D01, "ERROR_DEPLOYMENT_BLOCKED_BY_POLICY",                                             false, "Group policy blocks this deployment - ask an administrator to allow it."),
+    e(PackagesInUse,                                                     0x80073D02, "ERROR_PACKAGES_IN_USE",                                                          true,  "The package (or a dependency) is in use - close running apps and try again."),
+    e(RecoveryFileCorrupt,                                               0x80073D03, "ERROR_RECOVERY_FILE_CORRUPT",                                                    false, "Recovery data is corrupt - remove and reinstall the package."),
+    e(InvalidStagedSignature,                                            0x80073D04, "ERROR_INVALID_STAGED_SIGNATURE",                                                 false, "The staged package's signature is no longer valid - remove the staged package and reinstall."),
+    e(DeletingExistingApplicationdataStoreFailed,                        0x80073D05, "ERROR_DELETING_EXISTING_APPLICATIONDATA_STORE_FAILED",                           true,  "Old app data couldn't be deleted - close anything using it and try again."),
+    e(InstallPackageDowngrade,                                           0x80073D06, "ERROR_INSTALL_PACKAGE_DOWNGRADE",                                                false, "A newer version is already installed - remove it first, or use `-ForceUpdateFromAnyVersion`."),
+    e(SystemNeedsRemediation,                                            0x80073D07, "ERROR_SYSTEM_NEEDS_REMEDIATION",                                                 false, "A system app needs to be repaired - run Windows Update or repair Windows."),
+    e(AppxIntegrityFailureClrNgen,                                       0x80073D08, "ERROR_APPX_INTEGRITY_FAILURE_CLR_NGEN",                                          false, "Native image integrity checks failed - reinstall the package."),
+    e(ResiliencyFileCorrupt,                                             0x80073D09, "ERROR_RESILIENCY_FILE_CORRUPT",                                                  false, "Resiliency data is corrupt - remove and reinstall the package."),
+    e(InstallFirewallServiceNotRunning,                                  0x80073D0A, "ERROR_INSTALL_FIREWALL_SERVICE_NOT_RUNNING",                                     false, "The Windows Firewall service isn't running - start it (`Start-Service mpssvc`) and try again."),
+    e(PackageMoveFailed,                                                 0x80073D0B, "ERROR_PACKAGE_MOVE_FAILED",                                                      false, "The package couldn't be moved - check the target volume."),
+    e(InstallVolumeNotEmpty,                                             0x80073D0C, "ERROR_INSTALL_VOLUME_NOT_EMPTY",                                                 false, "The target volume isn't empty - pick an empty volume."),
+    e(InstallVolumeOffline,                                              0x80073D0D, "ERROR_INSTALL_VOLUME_OFFLINE",                                                   true,  "The target volume is offline - reconnect it and try again."),
+    e(InstallVolumeCorrupt,                                              0x80073D0E, "ERROR_INSTALL_VOLUME_CORRUPT",                                                   false, "The target volume is corrupt - repair it (`chkdsk`) or pick another volume."),
+    e(NeedsRegistration,                                                 0x80073D0F, "ERROR_NEEDS_REGISTRATION",                                                       false, "The package is staged but not registered for this user - register it (`Add-AppxPackage -Register`)."),
+    e(InstallWrongProcessorArchitecture,                                 0x80073D10, "ERROR_INSTALL_WRONG_PROCESSOR_ARCHITECTURE",                                     false, "The package doesn't support this processor architecture - install a matching package or bundle."),
+    e(DevSideloadLimitExceeded,                                          0x80073D11, "ERROR_DEV_SIDELOAD_LIMIT_EXCEEDED",                                              false, "The developer sideloading limit was reached - remove other sideloaded packages, or enable developer mode."),
+    e(InstallOptionalPackageRequiresMainPackage,                         0x80073D12, "ERROR_INSTALL_OPTIONAL_PACKAGE_REQUIRES_MAIN_PACKAGE",                           false, "This optional package needs its main package - install the main package first."),
+    e(PackageNotSupportedOnFilesystem,                                   0x80073D13, "ERROR_PACKAGE_NOT_SUPPORTED_ON_FILESYSTEM",                                      false, "The target file system isn't supported - install to an NTFS volume."),
+    e(PackageMoveBlockedByStreaming,                                     0x80073D14, "ERROR_PACKAGE_MOVE_BLOCKED_BY_STREAMING",                                        true,  "The package is still streaming in - wait for it to finish and try again."),
+    e(InstallOptionalPackageApplicationidNotUnique,                      0x80073D15, "ERROR_INSTALL_OPTIONAL_PACKAGE_APPLICATIONID_NOT_UNIQUE",                        false, "An optional package's application ID collides with its main package - rename the application."),
+    e(PackageStagingOnhold,                                              0x80073D16, "ERROR_PACKAGE_STAGING_ONHOLD",                                                   true,  "Staging is on hold - try again later."),
+    e(InstallInvalidRelatedSetUpdate,                                    0x80073D17, "ERROR_INSTALL_INVALID_RELATED_SET_UPDATE",                                       false, "The related set update is invalid - update the main and optional packages together."),
+    e(InstallOptionalPackageRequiresMainPackageFulltrustCapability,      0x80073D18, "ERROR_INSTALL_OPTIONAL_PACKAGE_REQUIRES_MAIN_PACKAGE_FULLTRUST_CAPABILITY",      false, "This full trust optional package needs a main package declaring `runFullTrust`."),
+    e(DeploymentBlockedByUserLogOff,                                     0x80073D19, "ERROR_DEPLOYMENT_BLOCKED_BY_USER_LOG_OFF",                                       true,  "The user is logging off - try again after they log back in."),
+    e(ProvisionOptionalPackageRequiresMainPackageProvisioned,            0x80073D1A, "ERROR_PROVISION_OPTIONAL_PACKAGE_REQUIRES_MAIN_PACKAGE_PROVISIONED",             false, "Provision the main package before its optional packages."),
+    e(PackagesReputationCheckFailed,                                     0x80073D1B, "ERROR_PACKAGES_REPUTATION_CHECK_FAILED",                                         false, "SmartScreen's reputation check rejected the package - sign it with a reputable certificate."),
+    e(PackagesReputationCheckTimedout,                                   0x80073D1C, "ERROR_PACKAGES_REPUTATION_CHECK_TIMEDOUT",                                       true,  "SmartScreen's reputation check timed out - check network connectivity and try again."),
+    e(DeploymentOptionNotSupported,                                      0x80073D1D, "ERROR_DEPLOYMENT_OPTION_NOT_SUPPORTED",                                          false, "A deployment option isn't supported on this version of Windows - drop the option or update Windows."),
+    e(AppinstallerActivationBlocked,                                     0x80073D1E, "ERROR_APPINSTALLER_ACTIVATION_BLOCKED",                                          false, "App Installer activation is blocked - install via `Add-AppxPackage` instead."),
+    e(RegistrationFromRemoteDriveNotSupported,                           0x80073D1F, "ERROR_REGISTRATION_FROM_REMOTE_DRIVE_NOT_SUPPORTED",                             false, "Loose packages can't be registered from a network drive - copy them to a local drive first."),
+    e(AppxRawDataWriteFailed,                                            0x80073D20, "ERROR_APPX_RAW_DATA_WRITE_FAILED",                                               false, "Raw package data couldn't be written - check the target volume."),
+    e(DeploymentBlockedByVolumePolicyPackage,                            0x80073D21, "ERROR_DEPLOYMENT_BLOCKED_BY_VOLUME_POLICY_PACKAGE",                              false, "Policy blocks installing this package to a non-system volume - install to the system volume."),
+    e(DeploymentBlockedByVolumePolicyMachine,                            0x80073D22, "ERROR_DEPLOYMENT_BLOCKED_BY_VOLUME_POLICY_MACHINE",                              false, "Policy blocks installing packages to non-system volumes - install to the system volume."),
+    e(DeploymentBlockedByProfilePolicy,                                  0x80073D23, "ERROR_DEPLOYMENT_BLOCKED_BY_PROFILE_POLICY",                                     false, "Deployment is blocked for this user profile (e.g. a roaming or temporary profile) - use a regular profile."),
+    e(DeploymentFailedConflictingMutablePackageDirectory,                0x80073D24, "ERROR_DEPLOYMENT_FAILED_CONFLICTING_MUTABLE_PACKAGE_DIRECTORY",                  false, "The package's mutable directory conflicts with another package - remove the conflicting package."),
+    e(SingletonResourceInstalledInActiveUser,                            0x80073D25, "ERROR_SINGLETON_RESOURCE_INSTALLED_IN_ACTIVE_USER",                              true,  "A singleton resource is installed for another logged in user - try again once they log off."),
+    e(DifferentVersionOfPackagedServiceInstalled,                        0x80073D26, "ERROR_DIFFERENT_VERSION_OF_PACKAGED_SERVICE_INSTALLED",                          false, "Another version of this packaged service is installed - remove it first."),
+    e(ServiceExistsAsNonPackagedService,                                 0x80073D27, "ERROR_SERVICE_EXISTS_AS_NON_PACKAGED_SERVICE",                                   false, "A non-packaged service with the same name already exists - remove or rename it."),
+    e(PackagedServiceRequiresAdminPrivileges,                            0x80073D28, "ERROR_PACKAGED_SERVICE_REQUIRES_ADMIN_PRIVILEGES",                               false, "Packages with services must be installed from an elevated prompt."),
+    e(RedirectionToDefaultAccountNotAllowed,                             0x80073D29, "ERROR_REDIRECTION_TO_DEFAULT_ACCOUNT_NOT_ALLOWED",                               false, "Redirecting the deployment to the default account isn't allowed - install for a specific user."),
+    e(PackageLacksCapabilityToDeployOnHost,                              0x80073D2A, "ERROR_PACKAGE_LACKS_CAPABILITY_TO_DEPLOY_ON_HOST",                               false, "The package lacks the capability required to deploy on its host - add it to the manifest."),
+    e(UnsignedPackageInvalidContent,                                     0x80073D2B, "ERROR_UNSIGNED_PACKAGE_INVALID_CONTENT",                                         false, "Unsigned packages may not contain executable content here - sign the package."),
+    e(UnsignedPackageInvalidPublisherNamespace,                          0x80073D2C, "ERROR_UNSIGNED_PACKAGE_INVALID_PUBLISHER_NAMESPACE",                             false, "Unsigned packages must use the unsigned publisher namespace (`OID.2.25.311729368913984317654407730594956997722=1`)."),
+    e(SignedPackageInvalidPublisherNamespace,                            0x80073D2D, "ERROR_SIGNED_PACKAGE_INVALID_PUBLISHER_NAMESPACE",                               false, "Signed packages must not use the unsigned publisher namespace - fix the manifest's `Publisher`."),
+    e(PackageExternalLocationNotAllowed,                                 0x80073D2E, "ERROR_PACKAGE_EXTERNAL_LOCATION_NOT_ALLOWED",                                    false, "The package may not be installed with an external location - drop `-ExternalLocation`."),
+    e(InstallFulltrustHostruntimeRequiresMainPackageFulltrustCapability, 0x80073D2F, "ERROR_INSTALL_FULLTRUST_HOSTRUNTIME_REQUIRES_MAIN_PACKAGE_FULLTRUST_CAPABILITY", false, "A full trust host runtime needs a main package declaring `runFullTrust`."),
+
+    // https://docs.microsoft.com/en-us/windows/win32/seccrypto/common-hresult-values
+    e(TrustNoSignature,                                                  0x800B0100, "TRUST_E_NOSIGNATURE",                                                            false, "The package isn't signed - sign it with `SignTool sign`."),
+    e(CertExpired,                                                       0x800B0101, "CERT_E_EXPIRED",                                                                 false, "The signing certificate has expired - re-sign with a current certificate, or timestamp future signatures."),
+    e(CertUntrustedRoot,                                                 0x800B0109, "CERT_E_UNTRUSTEDROOT",                                                           false, "The signing certificate isn't trusted - install it into `Cert:\\LocalMachine\\TrustedPeople` (or `Root`)."),
+    e(CertChaining,                                                      0x800B010A, "CERT_E_CHAINING",                                                                false, "The signing certificate's chain couldn't be built - install the intermediate certificates."),
+    e(CertRevoked,                                                       0x800B010C, "CERT_E_REVOKED",                                                                 false, "The signing certificate was revoked - re-sign with a new certificate."),
+    e(TrustCertSignature,                                                0x80096004, "TRUST_E_CERT_SIGNATURE",                                                         false, "The signing certificate's own signature is invalid - re-sign with a valid certificate."),
+    e(TrustBadDigest,                                                    0x80096010, "TRUST_E_BAD_DIGEST",                                                             false, "The package was modified after signing - rebuild and re-sign it."),
+    e(AppxInvalidSipClientData,                                          0x80080209, "APPX_E_INVALID_SIP_CLIENT_DATA",                                                 false, "The signing parameters don't match the package - sign with `SignTool sign /fd SHA256`."),
+]};
+
+impl AppxError {
+    /// Decode `hresult`, or [Other](Self::Other) if it's not a known deployment error
+    pub fn from_hresult(hresult: u32) -> Self { CATALOG.iter().find(|e| e.hresult == hresult).map_or(Self::Other(hresult), |e| e.error) }
+
+    /// Decode the first `HRESULT: 0x...` found in `text` (e.g. a PowerShell error record), if any
+    pub fn parse(text: &str) -> Option<Self> { parse_hresult(text).map(Self::from_hresult) }
+
+    /// Decode the [PowerShellError] wrapped by `err` (e.g. from [add_appx_package](crate::repository::add_appx_package)), if any
+    pub fn from_io_error(err: &io::Error) -> Option<Self> { err.get_ref()?.downcast_ref::<PowerShellError>()?.appx_error() }
+
+    /// The HRESULT (e.g. `0x80073D02`)
+    pub fn hresult(self) -> u32 { match self { Self::Other(hresult) => hresult, _ => self.entry().expect("AppxError missing from CATALOG").hresult } }
+
+    /// The `winerror.h` name (e.g. `ERROR_PACKAGES_IN_USE`), or [None] for [Other](Self::Other)
+    pub fn name(self) -> Option<&'static str> { self.entry().map(|e| e.name) }
+
+    /// A human-readable suggestion for fixing the failure
+    pub fn remediation(self) -> &'static str {
+        self.entry().map_or("Unrecognized HRESULT - run `Get-AppPackageLog -ActivityID ...` for details.", |e| e.remediation)
+    }
+
+    /// The same deployment might succeed if simply retried later (e.g. once apps are closed or the network recovers),
+    /// rather than needing the package, machine or policy to change first
+    pub fn is_retriable(self) -> bool { matches!(self.entry(), Some(e) if e.retriable) }
+
+    /// The failure is due to the package's signature or the trust of its signing certificate
+    pub fn is_signature_error(self) -> bool {
+        use AppxError::*;
+        matches!(self,
+            InvalidStagedSignature | UnsignedPackageInvalidContent | UnsignedPackageInvalidPublisherNamespace | SignedPackageInvalidPublisherNamespace |
+            TrustNoSignature | CertExpired | CertUntrustedRoot | CertChaining | CertRevoked | TrustCertSignature | TrustBadDigest | AppxInvalidSipClientData
+        )
+    }
+
+    fn entry(self) -> Option<&'static Entry> { CATALOG.iter().find(|e| e.error == self) }
+}
+
+impl Error   for AppxError {}
+impl Display for AppxError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name)  => write!(fmt, "{} (0x{:08X})", name, self.hresult()),
+            None        => write!(fmt, "HRESULT 0x{:08X}", self.hresult()),
+        }
+    }
+}
+
+
+
+/// Find the first `HRESULT: 0x...` (or `HRESULT 0x...`) in `text`
+pub(crate) fn parse_hresult(text: &str) -> Option<u32> {
+    text.match_indices("HRESULT").find_map(|(i, m)| {
+        let rest = text[i + m.len() ..].trim_start_matches(|ch: char| ch == ':' || ch.is_whitespace());
+        let hex = rest.strip_prefix("0x").or_else(|| rest.strip_prefix("0X"))?;
+        let digits = hex.find(|ch: char| !ch.is_ascii_hexdigit()).unwrap_or(hex.len());
+        if digits == 0 || digits > 8 { return None; }
+        u32::from_str_radix(&hex[.. digits], 16).ok()
+    })
+}
+
+/// Find the GUID following `[ActivityId]` in `text` (the ID to pass to `Get-AppPackageLog -ActivityID`)
+pub(crate) fn parse_activity_id(text: &str) -> Option<&str> {
+    let rest = text[text.find("[ActivityId]")? + "[ActivityId]".len() ..].trim_start();
+    let len = rest.find(|ch: char| !(ch.is_ascii_hexdigit() || ch == '-')).unwrap_or(rest.len());
+    let id = &rest[.. len];
+    if id.len() == 36 && id.bytes().filter(|&b| b == b'-').count() == 4 { Some(id) } else { None }
+}
+
+
+
+#[test] fn test_appx_error() {
+    use AppxError::*;
+
+    for hresult in 0x80073CF0 ..= 0x80073D2F {
+        let err = AppxError::from_hresult(hresult);
+        assert!(matches!(err.name(), Some(name) if name.starts_with("ERROR_")), "0x{:08X} not in catalog", hresult);
+        assert_eq!(err.hresult(), hresult);
+        assert!(!err.remediation().is_empty());
+    }
+    for entry in CATALOG.iter() {
+        assert_eq!(AppxError::from_hresult(entry.hresult), entry.error);
+        assert_eq!(CATALOG.iter().filter(|e| e.error == entry.error).count(), 1, "{} duplicated", entry.name);
+    }
+
+    assert_eq!(AppxError::from_hresult(0x80073CF9), InstallFailed);
+    assert_eq!(AppxError::from_hresult(0x80073CFB), PackageAlreadyExists);
+    assert_eq!(AppxError::from_hresult(0x80073D06), InstallPackageDowngrade);
+    assert_eq!(AppxError::from_hresult(0x80004005), Other(0x80004005));
+    assert_eq!(Other(0x80004005).name(), None);
+    assert_eq!(Other(0x80004005).hresult(), 0x80004005);
+    assert!(!Other(0x80004005).is_retriable());
+    assert_eq!(InstallPackageDowngrade.to_string(), "ERROR_INSTALL_PACKAGE_DOWNGRADE (0x80073D06)");
+    assert_eq!(Other(0x80004005).to_string(), "HRESULT 0x80004005");
+
+    assert!( PackagesInUse.is_retriable());
+    assert!(!PackageAlreadyExists.is_retriable());
+    assert!( CertUntrustedRoot.is_signature_error());
+    assert!(!PackagesInUse.is_signature_error());
+
+    let record = concat!(
+        "Add-AppxPackage : Deployment failed with HRESULT: 0x80073D02, The package could not be installed because resources it modifies are currently in use.\r\n",
+        "error 0x80073D02: Unable to install because the following apps need to be closed Example_1.2.3.4_x64__8wekyb3d8bbwe.\r\n",
+        "NOTE: For additional information, look for [ActivityId] 3a5f1c2e-8b9d-0004-c1e2-5f3a1c2e8b9d in the Event Log or use the command line Get-AppPackageLog -ActivityID 3a5f1c2e-8b9d-0004-c1e2-5f3a1c2e8b9d\r\n",
+        "At line:1 char:1\r\n",
+    );
+    assert_eq!(parse_hresult(record),       Some(0x80073D02));
+    assert_eq!(AppxError::parse(record),    Some(PackagesInUse));
+    assert_eq!(parse_activity_id(record),   Some("3a5f1c2e-8b9d-0004-c1e2-5f3a1c2e8b9d"));
+    assert_eq!(parse_hresult("(Exception from HRESULT: 0x80073cfb)"), Some(0x80073CFB));
+    assert_eq!(parse_hresult("HRESULT: 0x"),                            None);
+    assert_eq!(parse_hresult("HRESULT: 0x123456789"),                   None);
+    assert_eq!(parse_hresult("exit code 1"),                            None);
+    assert_eq!(parse_activity_id("[ActivityId] not-a-guid"),            None);
+}
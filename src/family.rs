@@ -1,7 +1,14 @@
-use crate::WString;
+use crate::{PackageIdError, PublisherId, Sid, WString};
+use crate::{ordinal, validate};
 
+#[cfg(feature = "serde")] use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::cmp::Ordering;
+use std::convert::{TryFrom, TryInto};
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
 
 
 
@@ -17,6 +24,11 @@ use std::fmt::{self, Display, Formatter};
 ///
 /// To refer to specific individual packages within the set, see [PackageFullName](crate::PackageFullName)
 ///
+/// ### Validation
+///
+/// [new](Self::new), [FromStr] and [TryFrom] validate the name per the manifest rules, returning [PackageIdError] on failure.
+/// [new_unchecked](Self::new_unchecked) accepts anything (e.g. names enumerated from the registry).
+///
 /// ### Examples
 ///
 /// * `NcsiUwpApp_8wekyb3d8bbwe`
@@ -25,23 +37,80 @@ use std::fmt::{self, Display, Formatter};
 /// * `CanonicalGroupLimited.Ubuntu18.04onWindows_79rhkp1fndgsc`
 /// * `CanonicalGroupLimited.Ubuntu20.04onWindows_79rhkp1fndgsc`
 ///
+/// ### Comparisons
+///
+/// Like Windows, [PartialEq], [Ord] and [Hash] ignore case (ordinally, ala `CompareStringOrdinal(..., TRUE)`):
+/// `NcsiUwpApp_8wekyb3d8bbwe` == `ncsiuwpapp_8wekyb3d8bbwe`.
+/// Compare the underlying [WString]s (`*a == *b`) for exact comparisons.
+///
 /// ### Corresponds to
 ///
 /// `powershell Get-AppxPackage ^| Format-Table -Property PackageFamilyName`<br>
 /// `HKCR\Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Families\...`<br>
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)] pub struct PackageFamilyName(pub(crate) WString);
+#[derive(Clone, Debug)] pub struct PackageFamilyName(pub(crate) WString);
 
 impl Deref              for PackageFamilyName { fn deref(&self) -> &Self::Target { &self.0 } type Target = WString; }
+impl PartialEq          for PackageFamilyName { fn eq(&self, other: &Self) -> bool { ordinal::eq_ignore_case(self.0.units(), other.0.units()) } }
+impl Eq                 for PackageFamilyName {}
+impl PartialOrd         for PackageFamilyName { fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) } }
+impl Ord                for PackageFamilyName { fn cmp(&self, other: &Self) -> Ordering { ordinal::cmp_ignore_case(self.0.units(), other.0.units()) } }
+impl Hash               for PackageFamilyName { fn hash<H: Hasher>(&self, state: &mut H) { ordinal::hash_ignore_case(self.0.units(), state) } }
 impl Display            for PackageFamilyName { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { Display::fmt(&self.0.display(), fmt) } }
-impl From<&String  >    for PackageFamilyName { fn from(value: &String  ) -> Self { Self(WString::from(value)) } }
-impl From< String  >    for PackageFamilyName { fn from(value:  String  ) -> Self { Self(WString::from(value)) } }
-impl From<&str     >    for PackageFamilyName { fn from(value: &str     ) -> Self { Self(WString::from(value)) } }
-impl From<&   [u16]>    for PackageFamilyName { fn from(value: &   [u16]) -> Self { Self(WString::from(value)) } }
-impl From<&Vec<u16>>    for PackageFamilyName { fn from(value: &Vec<u16>) -> Self { Self(WString::from(value)) } }
-impl From< Vec<u16>>    for PackageFamilyName { fn from(value:  Vec<u16>) -> Self { Self(WString::from(value)) } }
+#[cfg(feature = "serde")] impl Serialize          for PackageFamilyName { fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> { serializer.collect_str(self) } }
+#[cfg(feature = "serde")] impl<'de> Deserialize<'de> for PackageFamilyName { fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> { String::deserialize(deserializer).map(Self::new_unchecked) } }
+impl FromStr            for PackageFamilyName { fn from_str(value: &str) -> Result<Self, Self::Err> { Self::checked(WString::from(value)) } type Err = PackageIdError; }
+impl TryFrom<&String  > for PackageFamilyName { fn try_from(value: &String  ) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom< String  > for PackageFamilyName { fn try_from(value:  String  ) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&str     > for PackageFamilyName { fn try_from(value: &str     ) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&   [u16]> for PackageFamilyName { fn try_from(value: &   [u16]) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&Vec<u16>> for PackageFamilyName { fn try_from(value: &Vec<u16>) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom< Vec<u16>> for PackageFamilyName { fn try_from(value:  Vec<u16>) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&WString > for PackageFamilyName { fn try_from(value: &WString ) -> Result<Self, Self::Error> { Self::checked(value.clone())       } type Error = PackageIdError; }
+impl TryFrom< WString > for PackageFamilyName { fn try_from(value:  WString ) -> Result<Self, Self::Error> { Self::checked(value)               } type Error = PackageIdError; }
 
 impl PackageFamilyName {
-    pub fn new(pfn: impl Into<Self>) -> Self { pfn.into() }
+    /// Parse and validate a [PackageFamilyName]
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// assert!(appx::PackageFamilyName::new("NcsiUwpApp_8wekyb3d8bbwe").is_ok());
+    /// assert!(appx::PackageFamilyName::new("NcsiUwpApp").is_err());
+    /// ```
+    pub fn new(pfn: impl TryInto<Self, Error = PackageIdError>) -> Result<Self, PackageIdError> { pfn.try_into() }
+
+    /// Construct a [PackageFamilyName] **without** validating it (e.g. for names enumerated from the registry).
+    /// Accessors like [name](Self::name) return `&[]` for missing fields.
+    pub fn new_unchecked(pfn: impl Into<WString>) -> Self { Self(pfn.into()) }
+
+    fn checked(pfn: WString) -> Result<Self, PackageIdError> {
+        let pfn = Self(pfn);
+        pfn.validate()?;
+        Ok(pfn)
+    }
+
+    /// Check that this is a `Name_PublisherId` with a valid `Name` and `PublisherId`
+    pub fn validate(&self) -> Result<(), PackageIdError> {
+        let fields = validate::fields(self.0.units(), 2)?;
+        validate::name(fields[0])?;
+        validate::publisher_id(fields[1])?;
+        Ok(())
+    }
+
+    /// Predict the [PackageFamilyName] of a package from its manifest's `Identity@Name` and `Identity@Publisher`
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// let fam = appx::PackageFamilyName::from_name_and_publisher(
+    ///     "NcsiUwpApp",
+    ///     "CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US",
+    /// ).unwrap();
+    /// assert_eq!(fam.to_string(), "NcsiUwpApp_8wekyb3d8bbwe");
+    /// ```
+    pub fn from_name_and_publisher(name: &str, publisher: &str) -> Result<Self, PackageIdError> {
+        Self::new(format!("{}_{}", name, PublisherId::from_publisher(publisher)))
+    }
 
     /// Family `Name`
     ///
@@ -56,6 +125,24 @@ impl PackageFamilyName {
         }
     }
 
+    /// The [AppContainer](https://docs.microsoft.com/en-us/windows/win32/secauthz/appcontainer-isolation) package SID
+    /// (`S-1-15-2-...`) of this family, computed the same way as [DeriveAppContainerSidFromAppContainerName]:
+    /// the first 7 little endian `u32`s of the SHA-256 of the lowercased (UTF-16LE) family name.
+    ///
+    /// Doesn't require the package to be installed (or even a Windows machine.)
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// let fam = appx::PackageFamilyName::new("Microsoft.MicrosoftEdge_8wekyb3d8bbwe").unwrap();
+    /// assert_eq!(fam.appcontainer_sid().to_string(), "S-1-15-2-3624051433-2125758914-1423191267-1740899205-1073925389-3782572162-737981194");
+    /// ```
+    ///
+    /// [DeriveAppContainerSidFromAppContainerName]:    https://docs.microsoft.com/en-us/windows/win32/api/userenv/nf-userenv-deriveappcontainersidfromappcontainername
+    pub fn appcontainer_sid(&self) -> Sid {
+        crate::sid::app_package_sid_from_hash(&[2], self.0.units().iter().map(|&cu| ordinal::downcase(cu)), 7)
+    }
+
     /// Family `PublisherId`
     ///
     /// ### Examples
@@ -71,11 +158,37 @@ impl PackageFamilyName {
 }
 
 #[test] fn test_pfn() {
-    let pfn = PackageFamilyName::from("NcsiUwpApp_8wekyb3d8bbwe");
+    let pfn = PackageFamilyName::new("NcsiUwpApp_8wekyb3d8bbwe").unwrap();
     assert_eq!(pfn.name(),          wchar::wch!("NcsiUwpApp"));
     assert_eq!(pfn.publisher_id(),  wchar::wch!("8wekyb3d8bbwe"));
 
-    let pfn = PackageFamilyName::from("CanonicalGroupLimited.UbuntuonWindows_79rhkp1fndgsc");
+    let pfn = PackageFamilyName::new("CanonicalGroupLimited.UbuntuonWindows_79rhkp1fndgsc").unwrap();
     assert_eq!(pfn.name(),          wchar::wch!("CanonicalGroupLimited.UbuntuonWindows"));
     assert_eq!(pfn.publisher_id(),  wchar::wch!("79rhkp1fndgsc"));
+
+    let pfn = PackageFamilyName::new_unchecked("garbage");
+    assert_eq!(pfn.name(),          wchar::wch!("garbage"));
+    assert_eq!(pfn.publisher_id(),  &[] as &[u16]);
+    assert_eq!(pfn.validate(),      Err(PackageIdError::FieldCount { expected: 2, found: 1 }));
+
+    assert_eq!("NcsiUwpApp_8wekyb3d8bbwe_x".parse::<PackageFamilyName>(),  Err(PackageIdError::FieldCount { expected: 2, found: 3 }));
+    assert_eq!("CON_8wekyb3d8bbwe"         .parse::<PackageFamilyName>(),  Err(PackageIdError::NameReserved));
+    assert!(matches!("NcsiUwpApp_8wekyb3d8bbw".parse::<PackageFamilyName>(), Err(PackageIdError::PublisherId(_))));
+    assert_eq!(PackageFamilyName::from_name_and_publisher("a", "CN=Foo"),  Err(PackageIdError::NameLength(1)));
+}
+
+#[test] fn test_pfn_ignore_case() {
+    use std::collections::HashSet;
+
+    let a = PackageFamilyName::new("NcsiUwpApp_8wekyb3d8bbwe").unwrap();
+    let b = PackageFamilyName::new("ncsiuwpapp_8WEKYB3D8BBWE").unwrap(); // uppercase publisher IDs validate too
+    assert_eq!(a, b);
+    assert_ne!(*a, *b);
+    assert_eq!(a.cmp(&b), Ordering::Equal);
+
+    let set = vec![a.clone(), b.clone()].into_iter().collect::<HashSet<_>>();
+    assert_eq!(set.len(), 1);
+    assert!(set.contains(&PackageFamilyName::new_unchecked("NCSIUWPAPP_8wekyb3d8bbwe")));
+
+    assert!(PackageFamilyName::new_unchecked("a_x") < PackageFamilyName::new_unchecked("B_x"));
 }
@@ -0,0 +1,405 @@
+//! Pure Rust reader for offline registry hives ([regf](https://github.com/msuhanov/regf/blob/master/Windows%20registry%20file%20format%20specification.md) files)
+//! such as a copied `UsrClass.dat`.
+//!
+//! Only the primary hive file is read: transaction logs (`*.LOG1`, `*.LOG2`) of a dirty hive aren't replayed.
+
+use crate::{ordinal, WString};
+use crate::registry::{RawValue, RegistryBackend, RegistryKey, RegType};
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+
+
+/// An offline registry hive file (e.g. `%LOCALAPPDATA%\Microsoft\Windows\UsrClass.dat` copied off another machine).
+///
+/// The hive's root key is treated as `HKEY_CLASSES_ROOT`, which is what `UsrClass.dat` contains (the per-user half of it, anyways.)
+///
+/// ### Examples
+///
+/// ```rust,no_run
+/// let hive = appx::registry::HiveRegistry::open("UsrClass.dat").unwrap();
+/// for fam in appx::repository::families_in(&hive).unwrap() {
+///     println!("{}", fam.unwrap());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HiveRegistry(Arc<Hive>);
+
+#[derive(Debug)]
+struct Hive {
+    bins:           Vec<u8>, // everything after the base block
+    root:           u32,
+    minor_version:  u32,
+}
+
+const BASE_BLOCK_SIZE       : usize = 4096;
+const BIG_DATA_SEGMENT_SIZE : usize = 16344;
+const KEY_COMP_NAME         : u16   = 0x0020;
+const VALUE_COMP_NAME       : u16   = 0x0001;
+const DATA_IS_RESIDENT      : u32   = 0x8000_0000;
+const NO_CELL               : u32   = 0xFFFF_FFFF;
+
+impl HiveRegistry {
+    /// Read and parse a hive file
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> { Self::from_bytes(std::fs::read(path)?) }
+
+    /// Parse a hive from its raw bytes
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> io::Result<Self> {
+        let mut bytes = bytes.into();
+        if bytes.len() < BASE_BLOCK_SIZE || &bytes[..4] != b"regf" { return Err(invalid_data("hive: missing `regf` base block")); }
+        let major_version   = u32_at(&bytes, 20)?;
+        let minor_version   = u32_at(&bytes, 24)?;
+        let root            = u32_at(&bytes, 36)?;
+        let bins_size       = u32_at(&bytes, 40)? as usize;
+        if major_version != 1 { return Err(invalid_data("hive: unsupported major version")); }
+
+        let mut bins = bytes.split_off(BASE_BLOCK_SIZE);
+        bins.truncate(bins_size);
+        if bins.get(..4) != Some(b"hbin") { return Err(invalid_data("hive: missing first `hbin`")); }
+
+        let hive = Hive { bins, root, minor_version };
+        hive.nk(root)?;
+        Ok(Self(Arc::new(hive)))
+    }
+}
+
+impl RegistryBackend for HiveRegistry {
+    fn open(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>> {
+        HiveKey::new(self.0.clone(), self.0.root).subkey(path)
+    }
+}
+
+
+
+struct HiveKey {
+    hive:       Arc<Hive>,
+    cell:       u32,
+    subkeys:    RefCell<Option<NamedCells>>, // nk cells, parsed on first use so enumeration isn't O(n²)
+    values:     RefCell<Option<NamedCells>>, // vk cells, ditto
+}
+
+/// `(cell, name)` pairs of a key's subkeys or values
+type NamedCells = Rc<[(u32, Vec<u16>)]>;
+
+impl HiveKey {
+    fn new(hive: Arc<Hive>, cell: u32) -> Self { Self { hive, cell, subkeys: Default::default(), values: Default::default() } }
+
+    fn subkeys(&self) -> io::Result<NamedCells> {
+        cached(&self.subkeys, || self.hive.subkeys(self.cell)?.into_iter().map(|sk| Ok((sk, self.hive.key_name(sk)?))).collect())
+    }
+
+    fn values(&self) -> io::Result<NamedCells> {
+        cached(&self.values, || self.hive.values(self.cell)?.into_iter().map(|vk| Ok((vk, self.hive.value_name(vk)?))).collect())
+    }
+}
+
+impl RegistryKey for HiveKey {
+    fn subkey(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>> {
+        let mut names = path.split(|&cu| cu == u16::from(b'\\')).filter(|name| !name.is_empty());
+        let first = match names.next() {
+            Some(first) => first,
+            None        => return Ok(Box::new(HiveKey::new(self.hive.clone(), self.cell))),
+        };
+        let mut cell = find(&self.subkeys()?, first)?;
+        for name in names {
+            cell = self.hive.subkeys(cell)?.into_iter()
+                .find(|&sk| matches!(self.hive.key_name(sk), Ok(n) if ordinal::eq_ignore_case(&n, name)))
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        }
+        Ok(Box::new(HiveKey::new(self.hive.clone(), cell)))
+    }
+
+    fn enum_key(&self, index: u32) -> io::Result<Option<WString>> {
+        Ok(self.subkeys()?.get(index as usize).map(|(_, name)| WString::from(name.clone())))
+    }
+
+    fn enum_value(&self, index: u32) -> io::Result<Option<(WString, RawValue)>> {
+        match self.values()?.get(index as usize) {
+            Some((vk, name))    => Ok(Some((WString::from(name.clone()), self.hive.value_data(*vk)?))),
+            None                => Ok(None),
+        }
+    }
+
+    fn value(&self, name: &[u16]) -> io::Result<RawValue> {
+        self.hive.value_data(find(&self.values()?, name)?)
+    }
+}
+
+fn cached(cache: &RefCell<Option<NamedCells>>, parse: impl FnOnce() -> io::Result<Vec<(u32, Vec<u16>)>>) -> io::Result<NamedCells> {
+    if let Some(list) = cache.borrow().as_ref() { return Ok(list.clone()); }
+    let list = Rc::<[_]>::from(parse()?);
+    *cache.borrow_mut() = Some(list.clone());
+    Ok(list)
+}
+
+fn find(list: &[(u32, Vec<u16>)], name: &[u16]) -> io::Result<u32> {
+    list.iter().find(|(_, n)| ordinal::eq_ignore_case(n, name)).map(|&(cell, _)| cell).ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+}
+
+
+
+impl Hive {
+    /// The data of the allocated cell at `offset` (relative to the first hbin), excluding the size prefix
+    fn cell(&self, offset: u32) -> io::Result<&[u8]> {
+        let start = offset as usize;
+        let size = i32::from_le_bytes(<[u8; 4]>::try_from(self.bins.get(start..start.saturating_add(4)).ok_or_else(|| invalid_data("hive: cell out of bounds"))?).unwrap());
+        if size >= 0 { return Err(invalid_data("hive: reference to an unallocated cell")); }
+        let end = start.checked_add(size.unsigned_abs() as usize).ok_or_else(|| invalid_data("hive: cell out of bounds"))?;
+        self.bins.get(start+4..end).ok_or_else(|| invalid_data("hive: cell out of bounds"))
+    }
+
+    fn nk(&self, offset: u32) -> io::Result<&[u8]> {
+        let nk = self.cell(offset)?;
+        if nk.len() < 76 || &nk[..2] != b"nk" { return Err(invalid_data("hive: expected a `nk` key cell")); }
+        Ok(nk)
+    }
+
+    fn key_name(&self, offset: u32) -> io::Result<Vec<u16>> {
+        let nk = self.nk(offset)?;
+        let len = usize::from(u16_at(nk, 72)?);
+        let name = nk.get(76..76+len).ok_or_else(|| invalid_data("hive: key name out of bounds"))?;
+        Ok(decode_name(name, u16_at(nk, 2)? & KEY_COMP_NAME != 0))
+    }
+
+    /// The `nk` cells of all subkeys of the key at `offset`, in stored (sorted) order
+    fn subkeys(&self, offset: u32) -> io::Result<Vec<u32>> {
+        let nk = self.nk(offset)?;
+        let count = u32_at(nk, 20)?;
+        let mut subkeys = Vec::new();
+        if count != 0 { self.subkey_list(u32_at(nk, 28)?, &mut subkeys, 0)?; }
+        Ok(subkeys)
+    }
+
+    fn subkey_list(&self, offset: u32, subkeys: &mut Vec<u32>, depth: usize) -> io::Result<()> {
+        let list = self.cell(offset)?;
+        let count = usize::from(u16_at(list, 2)?);
+        match list.get(..2) {
+            Some(b"lf") | Some(b"lh") => for i in 0..count { subkeys.push(u32_at(list, 4 + 8*i)?); },
+            Some(b"li")               => for i in 0..count { subkeys.push(u32_at(list, 4 + 4*i)?); },
+            Some(b"ri") if depth == 0 => for i in 0..count { self.subkey_list(u32_at(list, 4 + 4*i)?, subkeys, depth + 1)?; },
+            _                         => return Err(invalid_data("hive: expected a `lf`, `lh`, `li`, or `ri` subkey list cell")),
+        }
+        Ok(())
+    }
+
+    /// The `vk` cells of all values of the key at `offset`
+    fn values(&self, offset: u32) -> io::Result<Vec<u32>> {
+        let nk = self.nk(offset)?;
+        let count = u32_at(nk, 36)? as usize;
+        let list_offset = u32_at(nk, 40)?;
+        if count == 0 || list_offset == NO_CELL { return Ok(Vec::new()); }
+        let list = self.cell(list_offset)?;
+        (0..count).map(|i| u32_at(list, 4*i)).collect()
+    }
+
+    fn vk(&self, offset: u32) -> io::Result<&[u8]> {
+        let vk = self.cell(offset)?;
+        if vk.len() < 20 || &vk[..2] != b"vk" { return Err(invalid_data("hive: expected a `vk` value cell")); }
+        Ok(vk)
+    }
+
+    fn value_name(&self, offset: u32) -> io::Result<Vec<u16>> {
+        let vk = self.vk(offset)?;
+        let len = usize::from(u16_at(vk, 2)?);
+        let name = vk.get(20..20+len).ok_or_else(|| invalid_data("hive: value name out of bounds"))?;
+        Ok(decode_name(name, u16_at(vk, 16)? & VALUE_COMP_NAME != 0))
+    }
+
+    fn value_data(&self, offset: u32) -> io::Result<RawValue> {
+        let vk = self.vk(offset)?;
+        let size        = u32_at(vk, 4)?;
+        let data_offset = u32_at(vk, 8)?;
+        let ty          = RegType(u32_at(vk, 12)?);
+
+        if size & DATA_IS_RESIDENT != 0 {
+            let len = (size & !DATA_IS_RESIDENT) as usize;
+            if len > 4 { return Err(invalid_data("hive: resident value data larger than 4 bytes")); }
+            return Ok(RawValue { ty, data: data_offset.to_le_bytes()[..len].to_vec() });
+        }
+
+        let len = size as usize;
+        if len == 0 { return Ok(RawValue { ty, data: Vec::new() }); }
+        let cell = self.cell(data_offset)?;
+        if len > BIG_DATA_SEGMENT_SIZE && self.minor_version >= 4 && cell.get(..2) == Some(b"db") {
+            let segments = usize::from(u16_at(cell, 2)?);
+            let list = self.cell(u32_at(cell, 4)?)?;
+            let mut data = Vec::with_capacity(len);
+            for i in 0..segments {
+                let segment = self.cell(u32_at(list, 4*i)?)?;
+                let n = (len - data.len()).min(BIG_DATA_SEGMENT_SIZE).min(segment.len());
+                data.extend_from_slice(&segment[..n]);
+            }
+            if data.len() != len { return Err(invalid_data("hive: big data segments too small")); }
+            return Ok(RawValue { ty, data });
+        }
+
+        let data = cell.get(..len).ok_or_else(|| invalid_data("hive: value data out of bounds"))?;
+        Ok(RawValue { ty, data: data.to_vec() })
+    }
+}
+
+/// Names are either "compressed" (Latin-1, one byte per code unit) or UTF-16LE
+fn decode_name(name: &[u8], compressed: bool) -> Vec<u16> {
+    if compressed {
+        name.iter().map(|&b| u16::from(b)).collect()
+    } else {
+        name.chunks_exact(2).map(|cu| u16::from_le_bytes([cu[0], cu[1]])).collect()
+    }
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> io::Result<u16> {
+    let b = bytes.get(offset..offset+2).ok_or_else(|| invalid_data("hive: read out of bounds"))?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> io::Result<u32> {
+    let b = bytes.get(offset..offset+4).ok_or_else(|| invalid_data("hive: read out of bounds"))?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn invalid_data(msg: &'static str) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, msg) }
+
+
+
+#[test] fn test_hive() {
+    use crate::{repository, PackageFamilyName, PackageFullName};
+
+    /// Minimal regf writer: one big hbin, cells appended in order
+    struct Writer(Vec<u8>);
+    impl Writer {
+        fn cell(&mut self, data: &[u8]) -> u32 {
+            let offset = self.0.len() as u32;
+            let size = (4 + data.len() + 7) & !7;
+            self.0.extend_from_slice(&(-(size as i32)).to_le_bytes());
+            self.0.extend_from_slice(data);
+            self.0.resize(offset as usize + size, 0);
+            offset
+        }
+        fn vk(&mut self, name: &str, ty: u32, data: &[u8]) -> u32 {
+            let (size, data_offset) = if data.len() <= 4 {
+                let mut inline = [0u8; 4];
+                inline[..data.len()].copy_from_slice(data);
+                (data.len() as u32 | DATA_IS_RESIDENT, u32::from_le_bytes(inline))
+            } else if data.len() <= BIG_DATA_SEGMENT_SIZE {
+                (data.len() as u32, self.cell(data))
+            } else {
+                let segments = data.chunks(BIG_DATA_SEGMENT_SIZE).map(|s| self.cell(s)).collect::<Vec<_>>();
+                let list = self.cell(&segments.iter().flat_map(|s| s.to_le_bytes()).collect::<Vec<_>>());
+                let mut db = b"db".to_vec();
+                db.extend_from_slice(&(segments.len() as u16).to_le_bytes());
+                db.extend_from_slice(&list.to_le_bytes());
+                (data.len() as u32, self.cell(&db))
+            };
+            let mut vk = b"vk".to_vec();
+            vk.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            vk.extend_from_slice(&size.to_le_bytes());
+            vk.extend_from_slice(&data_offset.to_le_bytes());
+            vk.extend_from_slice(&ty.to_le_bytes());
+            vk.extend_from_slice(&VALUE_COMP_NAME.to_le_bytes());
+            vk.extend_from_slice(&[0, 0]);
+            vk.extend_from_slice(name.as_bytes());
+            self.cell(&vk)
+        }
+        /// `list`: 0 = `lf`, 1 = `lh`, 2 = `li`, 3 = `ri` of `li`s
+        fn nk(&mut self, name: &str, utf16: bool, subkeys: &[u32], list: u8, values: &[u32]) -> u32 {
+            let subkey_list = match (subkeys.len(), list) {
+                (0, _) => NO_CELL,
+                (_, 0) | (_, 1) => {
+                    let mut l = if list == 0 { b"lf".to_vec() } else { b"lh".to_vec() };
+                    l.extend_from_slice(&(subkeys.len() as u16).to_le_bytes());
+                    for sk in subkeys { l.extend_from_slice(&sk.to_le_bytes()); l.extend_from_slice(&[0; 4]); }
+                    self.cell(&l)
+                },
+                (_, 2) | (_, _) => {
+                    let lis = subkeys.chunks(2).map(|chunk| {
+                        let mut l = b"li".to_vec();
+                        l.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+                        for sk in chunk { l.extend_from_slice(&sk.to_le_bytes()); }
+                        self.cell(&l)
+                    }).collect::<Vec<_>>();
+                    if list == 2 && lis.len() == 1 { lis[0] } else {
+                        let mut ri = b"ri".to_vec();
+                        ri.extend_from_slice(&(lis.len() as u16).to_le_bytes());
+                        for li in lis { ri.extend_from_slice(&li.to_le_bytes()); }
+                        self.cell(&ri)
+                    }
+                },
+            };
+            let value_list = if values.is_empty() { NO_CELL } else { self.cell(&values.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>()) };
+            let name = if utf16 { name.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<_>>() } else { name.as_bytes().to_vec() };
+            let mut nk = b"nk".to_vec();
+            nk.extend_from_slice(&(if utf16 { 0 } else { KEY_COMP_NAME }).to_le_bytes());
+            nk.resize(20, 0);
+            nk.extend_from_slice(&(subkeys.len() as u32).to_le_bytes());   // 20: subkey count
+            nk.extend_from_slice(&0u32.to_le_bytes());                      // 24: volatile subkey count
+            nk.extend_from_slice(&subkey_list.to_le_bytes());               // 28: subkey list
+            nk.extend_from_slice(&NO_CELL.to_le_bytes());                   // 32: volatile subkey list
+            nk.extend_from_slice(&(values.len() as u32).to_le_bytes());    // 36: value count
+            nk.extend_from_slice(&value_list.to_le_bytes());                // 40: value list
+            nk.resize(72, 0);
+            nk.extend_from_slice(&(name.len() as u16).to_le_bytes());      // 72: name length
+            nk.extend_from_slice(&0u16.to_le_bytes());                      // 74: class name length
+            nk.extend_from_slice(&name);
+            self.cell(&nk)
+        }
+    }
+
+    let pfn = "NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe";
+    let big = (0..40000u32).map(|i| i as u8).collect::<Vec<u8>>();
+    let mut w = Writer(vec![0; 32]);
+
+    let display_name = w.vk("DisplayName", 1, &"NcsiUwpApp\0".encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<_>>());
+    let supported_users = w.vk("SupportedUsers", 4, &1u32.to_le_bytes());
+    let os_min_version = w.vk("OSMinVersion", 11, &0x000A_0000_0000_0000u64.to_le_bytes());
+    let big = w.vk("Big", 3, &big);
+    let package = w.nk(pfn, true, &[], 0, &[display_name, supported_users, os_min_version, big]);
+    let packages = w.nk("Packages", false, &[package], 1, &[]);
+
+    let fam_pkg = w.nk(pfn, false, &[], 0, &[]);
+    let fams = ["CanonicalGroupLimited.UbuntuonWindows_79rhkp1fndgsc", "Microsoft.WindowsStore_8wekyb3d8bbwe", "NcsiUwpApp_8wekyb3d8bbwe"];
+    let fams = fams.iter().map(|&f| if f.starts_with("Ncsi") { w.nk(f, false, &[fam_pkg], 2, &[]) } else { w.nk(f, false, &[], 0, &[]) }).collect::<Vec<_>>();
+    let families = w.nk("Families", false, &fams, 3, &[]);
+
+    let mut key = w.nk("Repository", false, &[families, packages], 0, &[]);
+    for name in ["AppModel", "CurrentVersion", "Windows", "Microsoft", "Software", "Local Settings"].iter() { key = w.nk(name, false, &[key], 0, &[]); }
+    let root = w.nk("S-1-5-21-0000000000-0000000000-0000000000-1001_Classes", false, &[key], 0, &[]);
+
+    let bins_size = (w.0.len() + 4095) & !4095;
+    w.0.resize(bins_size, 0);
+    w.0[..4].copy_from_slice(b"hbin");
+    w.0[8..12].copy_from_slice(&(bins_size as u32).to_le_bytes());
+    let mut hive = vec![0u8; BASE_BLOCK_SIZE];
+    hive[..4].copy_from_slice(b"regf");
+    hive[20..24].copy_from_slice(&1u32.to_le_bytes());
+    hive[24..28].copy_from_slice(&5u32.to_le_bytes());
+    hive[36..40].copy_from_slice(&root.to_le_bytes());
+    hive[40..44].copy_from_slice(&(bins_size as u32).to_le_bytes());
+    hive.extend_from_slice(&w.0);
+
+    let hive = HiveRegistry::from_bytes(hive.clone()).unwrap();
+    let families = repository::families_in(&hive).unwrap().map(|f| f.unwrap().to_string()).collect::<Vec<_>>();
+    assert_eq!(families, ["CanonicalGroupLimited.UbuntuonWindows_79rhkp1fndgsc", "Microsoft.WindowsStore_8wekyb3d8bbwe", "NcsiUwpApp_8wekyb3d8bbwe"]);
+    assert_eq!(repository::packages_in(&hive).unwrap().map(|p| p.unwrap().to_string()).collect::<Vec<_>>(), [pfn]);
+    let ncsi = PackageFamilyName::new("NcsiUwpApp_8wekyb3d8bbwe").unwrap();
+    assert_eq!(repository::packages_for_family_in(&hive, &ncsi).unwrap().map(|p| p.unwrap().to_string()).collect::<Vec<_>>(), [pfn]);
+
+    let pfn = PackageFullName::new(pfn).unwrap();
+    assert_eq!(pfn.display_name_in(&hive).unwrap(),     "NcsiUwpApp");
+    assert_eq!(pfn.supported_users_in(&hive).unwrap(),  1);
+    assert_eq!(pfn.os_min_version_in(&hive).unwrap(),   0x000A_0000_0000_0000);
+    assert_eq!(pfn.os_max_version_tested_in(&hive).unwrap_err().kind(), io::ErrorKind::NotFound);
+    let names = pfn.raw_properties_in(&hive).unwrap().into_iter().map(|(name, _)| name.display().to_string()).collect::<Vec<_>>();
+    assert!(names.iter().any(|n| n == "DisplayName"));
+    assert!(names.iter().any(|n| n == "Big"));
+
+    let key = hive.open(wchar::wch!(r"local settings\software\microsoft\windows\currentversion\appmodel\repository\packages\ncsiuwpapp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe")).unwrap();
+    assert_eq!(key.value(wchar::wch!("Big")).unwrap().data, (0..40000u32).map(|i| i as u8).collect::<Vec<u8>>());
+
+    assert!(HiveRegistry::from_bytes(vec![0u8; 8192]).is_err());
+    assert!(HiveRegistry::from_bytes(b"regf".to_vec()).is_err());
+}
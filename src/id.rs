@@ -0,0 +1,242 @@
+use crate::{PackageFamilyName, PackageFullName, PackageIdError, PackageVersion, ProcessorArchitecture, PublisherId, ResourceId, WString};
+use crate::validate;
+
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+
+
+/// e.g. `{ Name: NcsiUwpApp, Version: 1000.19041.423.0, Architecture: neutral, ResourceId: neutral, PublisherId: 8wekyb3d8bbwe }` -
+/// A parsed and validated [PackageFullName].
+///
+/// Parses once (unlike the [PackageFullName] accessors, which re-split on every call) and round trips exactly:
+/// `PackageId::try_from(&pfn)?.full_name() == pfn`.
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::*;
+/// let pfn = PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap();
+/// let id  = pfn.id().unwrap();
+/// assert_eq!(id.name(), "NcsiUwpApp");
+/// assert_eq!(id.full_name(), pfn);
+///
+/// // bump the version
+/// let id = id.to_builder().version(PackageVersion::new(1000, 19041, 424, 0)).build().unwrap();
+/// assert_eq!(id.to_string(), "NcsiUwpApp_1000.19041.424.0_neutral_neutral_8wekyb3d8bbwe");
+/// ```
+///
+/// ### Corresponds to
+///
+/// [PACKAGE_ID](https://docs.microsoft.com/en-us/windows/win32/api/appmodel/ns-appmodel-package_id)<br>
+/// [Windows::ApplicationModel::PackageId](https://docs.microsoft.com/en-us/uwp/api/windows.applicationmodel.packageid)
+#[derive(Clone, Debug)]
+pub struct PackageId {
+    name:           String,
+    version:        PackageVersion,
+    architecture:   ProcessorArchitecture,
+    resource_id:    ResourceId,
+    publisher_id:   PublisherId,
+    parsed_from:    Option<PackageFullName>, // original casing (e.g. `X64`), if parsed rather than built
+}
+
+impl PackageId {
+    /// Start building a [PackageId] from scratch
+    pub fn builder() -> PackageIdBuilder { PackageIdBuilder::default() }
+
+    /// Start building a new [PackageId] from this one (e.g. to replace a single component)
+    pub fn to_builder(&self) -> PackageIdBuilder {
+        PackageIdBuilder {
+            name:           Some(self.name.clone()),
+            version:        Some(self.version),
+            architecture:   self.architecture,
+            resource_id:    self.resource_id.clone(),
+            publisher_id:   Some(self.publisher_id),
+        }
+    }
+
+    /// Package `Name` (e.g. `NcsiUwpApp`)
+    pub fn name(&self) -> &str { &self.name }
+
+    /// Package `Version` (e.g. `1000.19041.423.0`)
+    pub fn version(&self) -> PackageVersion { self.version }
+
+    /// Package `Architecture` (e.g. `neutral`)
+    pub fn architecture(&self) -> ProcessorArchitecture { self.architecture }
+
+    /// Package `ResourceId` (e.g. `neutral`)
+    pub fn resource_id(&self) -> &ResourceId { &self.resource_id }
+
+    /// Package `PublisherId` (e.g. `8wekyb3d8bbwe`)
+    pub fn publisher_id(&self) -> PublisherId { self.publisher_id }
+
+    /// e.g. `NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe`
+    pub fn full_name(&self) -> PackageFullName {
+        match self.parsed_from.as_ref() {
+            Some(pfn)   => pfn.clone(),
+            None        => PackageFullName(WString::from(self.to_string())),
+        }
+    }
+
+    /// e.g. `NcsiUwpApp_8wekyb3d8bbwe`
+    pub fn family_name(&self) -> PackageFamilyName {
+        match self.parsed_from.as_ref() {
+            Some(pfn)   => pfn.family_name(),
+            None        => PackageFamilyName(WString::from(format!("{}_{}", self.name, self.publisher_id))),
+        }
+    }
+
+    fn fields(&self) -> (&str, PackageVersion, ProcessorArchitecture, &ResourceId, PublisherId) {
+        (&self.name, self.version, self.architecture, &self.resource_id, self.publisher_id)
+    }
+}
+
+impl Display for PackageId {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self.parsed_from.as_ref() {
+            Some(pfn)   => Display::fmt(pfn, fmt),
+            None        => write!(fmt, "{}_{}_{}_{}_{}", self.name, self.version, self.architecture, self.resource_id, self.publisher_id),
+        }
+    }
+}
+
+// Compare parsed components only - the original casing of a parsed [PackageFullName] is just for display
+impl Eq         for PackageId {}
+impl PartialEq  for PackageId { fn eq(&self, other: &Self) -> bool { self.fields() == other.fields() } }
+impl Hash       for PackageId { fn hash<H: Hasher>(&self, state: &mut H) { self.fields().hash(state) } }
+
+impl TryFrom<&PackageFullName> for PackageId {
+    type Error = PackageIdError;
+    fn try_from(pfn: &PackageFullName) -> Result<Self, Self::Error> {
+        let fields = validate::fields(pfn.units(), 5)?;
+        validate::name(fields[0])?;
+        validate::architecture(fields[2])?;
+        validate::resource_id(fields[3])?;
+        Ok(Self {
+            name:           String::from_utf16_lossy(fields[0]),
+            version:        PackageVersion::from_units(fields[1])?,
+            architecture:   ProcessorArchitecture::from_units(fields[2]).unwrap_or(ProcessorArchitecture::Unknown),
+            resource_id:    ResourceId::from_units(fields[3]),
+            publisher_id:   PublisherId::from_units(fields[4])?,
+            parsed_from:    Some(pfn.clone()),
+        })
+    }
+}
+
+impl FromStr            for PackageId           { fn from_str(s: &str) -> Result<Self, Self::Err> { Self::try_from(&s.parse::<PackageFullName>()?) } type Err = PackageIdError; }
+impl From<&PackageId>   for PackageFullName     { fn from(id: &PackageId) -> Self { id.full_name() } }
+impl From< PackageId>   for PackageFullName     { fn from(id:  PackageId) -> Self { id.full_name() } }
+impl From<&PackageId>   for PackageFamilyName   { fn from(id: &PackageId) -> Self { id.family_name() } }
+impl From< PackageId>   for PackageFamilyName   { fn from(id:  PackageId) -> Self { id.family_name() } }
+
+
+
+/// Builds a validated [PackageId].  `name`, `version` and `publisher_id` (or `publisher`) are required.
+/// `architecture` defaults to `neutral`, `resource_id` defaults to (blank).
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::*;
+/// let id = PackageId::builder()
+///     .name("NcsiUwpApp")
+///     .version("1000.19041.423.0".parse().unwrap())
+///     .resource_id(ResourceId::Neutral)
+///     .publisher("CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US")
+///     .build()
+///     .unwrap();
+/// assert_eq!(id.to_string(), "NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe");
+/// ```
+#[derive(Clone, Debug)]
+pub struct PackageIdBuilder {
+    name:           Option<String>,
+    version:        Option<PackageVersion>,
+    architecture:   ProcessorArchitecture,
+    resource_id:    ResourceId,
+    publisher_id:   Option<PublisherId>,
+}
+
+impl Default for PackageIdBuilder {
+    fn default() -> Self {
+        Self {
+            name:           None,
+            version:        None,
+            architecture:   ProcessorArchitecture::Neutral,
+            resource_id:    ResourceId::None,
+            publisher_id:   None,
+        }
+    }
+}
+
+impl PackageIdBuilder {
+    pub fn name(self, name: impl Into<String>) -> Self { Self { name: Some(name.into()), ..self } }
+    pub fn version(self, version: PackageVersion) -> Self { Self { version: Some(version), ..self } }
+    pub fn architecture(self, architecture: ProcessorArchitecture) -> Self { Self { architecture, ..self } }
+    pub fn resource_id(self, resource_id: ResourceId) -> Self { Self { resource_id, ..self } }
+    pub fn publisher_id(self, publisher_id: PublisherId) -> Self { Self { publisher_id: Some(publisher_id), ..self } }
+
+    /// Set the `publisher_id` by hashing a publisher distinguished name (see [PublisherId::from_publisher])
+    pub fn publisher(self, publisher: &str) -> Self { self.publisher_id(PublisherId::from_publisher(publisher)) }
+
+    /// Validate and build the [PackageId]
+    pub fn build(self) -> Result<PackageId, PackageIdError> {
+        let name = self.name.ok_or(PackageIdError::MissingField("name"))?;
+        validate::name(&name.encode_utf16().collect::<Vec<u16>>())?;
+        validate::architecture(&self.architecture.as_str().encode_utf16().collect::<Vec<u16>>())?;
+        validate::resource_id(&self.resource_id.to_string().encode_utf16().collect::<Vec<u16>>())?;
+        Ok(PackageId {
+            name,
+            version:        self.version.ok_or(PackageIdError::MissingField("version"))?,
+            architecture:   self.architecture,
+            resource_id:    self.resource_id,
+            publisher_id:   self.publisher_id.ok_or(PackageIdError::MissingField("publisher_id"))?,
+            parsed_from:    None,
+        })
+    }
+}
+
+
+
+#[test] fn test_package_id() {
+    for &pfn in &[
+        "NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe",
+        "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc",
+        "CanonicalGroupLimited.Ubuntu20.04onWindows_2004.2020.812.0_neutral_split.scale-100_79rhkp1fndgsc",
+        "Microsoft.WindowsStore_12010.1001.3.0_neutral_~_8wekyb3d8bbwe",
+    ] {
+        let pfn = PackageFullName::new(pfn).unwrap();
+        let id = pfn.id().unwrap();
+        assert_eq!(id.full_name(), pfn);
+        assert_eq!(id.family_name(), pfn.family_name());
+        assert_eq!(id.to_builder().build().unwrap(), id);
+    }
+
+    let id : PackageId = "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc".parse().unwrap();
+    assert_eq!(id.name(),           "CanonicalGroupLimited.UbuntuonWindows");
+    assert_eq!(id.version(),        PackageVersion::new(2004, 2020, 812, 0));
+    assert_eq!(id.architecture(),   ProcessorArchitecture::X64);
+    assert_eq!(id.resource_id(),    &ResourceId::None);
+    assert_eq!(id.publisher_id(),   "79rhkp1fndgsc".parse::<PublisherId>().unwrap());
+    assert_eq!(id.family_name().to_string(), "CanonicalGroupLimited.UbuntuonWindows_79rhkp1fndgsc");
+
+    let arm = id.to_builder().architecture(ProcessorArchitecture::Arm64).build().unwrap();
+    assert_eq!(arm.to_string(), "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_arm64__79rhkp1fndgsc");
+
+    assert_eq!(id.to_builder().name("CON").build(),                                     Err(PackageIdError::NameReserved));
+    assert!(matches!(id.to_builder().architecture(ProcessorArchitecture::Unknown).build(), Err(PackageIdError::Architecture(_))));
+    assert_eq!(id.to_builder().resource_id(ResourceId::Other("a b".into())).build(),   Err(PackageIdError::ResourceId));
+    assert_eq!(PackageId::builder().name("Foo").build(),                                Err(PackageIdError::MissingField("version")));
+    assert!(matches!("Foo_1.0.0.0_x65__8wekyb3d8bbwe".parse::<PackageId>(),            Err(PackageIdError::Architecture(_))));
+
+    // architectures parse ignoring case, and round trip exactly
+    let mixed = PackageFullName::new("Foo_1.0.0.0_X64__8WEKYB3D8BBWE").unwrap();
+    let id = mixed.id().unwrap();
+    assert_eq!(id.architecture(),   ProcessorArchitecture::X64);
+    assert_eq!(id.to_string(),      "Foo_1.0.0.0_X64__8WEKYB3D8BBWE");
+    assert_eq!(*id.full_name(),     *mixed);
+    assert_eq!(id.family_name().to_string(), "Foo_8WEKYB3D8BBWE");
+    assert_eq!(id, "Foo_1.0.0.0_x64__8wekyb3d8bbwe".parse::<PackageId>().unwrap()); // the original casing doesn't affect equality
+    assert_eq!(id.to_builder().build().unwrap().to_string(), "Foo_1.0.0.0_x64__8wekyb3d8bbwe");
+}
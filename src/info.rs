@@ -0,0 +1,158 @@
+use crate::{Capability, PackageFullName, Sid, WString};
+use crate::{ordinal, reg};
+use crate::registry::{RawValue, RegistryBackend, Win32Registry};
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+
+
+/// A snapshot of the properties of an installed [PackageFullName].
+///
+/// Reads every value of `HKCR\...\Repository\Packages\<pfn>` with a single key open, then decodes them all up front
+/// (unlike the [PackageFullName] getters, which reopen the key for every call).
+/// Missing or malformed values are [None] - the undecoded values remain available via [raw_properties](Self::raw_properties).
+///
+/// ### Examples
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> {
+/// # if !cfg!(windows) { return Ok(()); }
+/// for info in appx::repository::package_infos()? {
+///     let info = info?;
+///     println!("{}: {:?} @ {:?}", info.full_name(), info.display_name(), info.package_root_folder());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ### Corresponds to
+///
+/// `powershell Get-AppxPackage`<br>
+/// `HKCR\Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages\...`<br>
+#[derive(Clone, Debug)]
+pub struct PackageInfo {
+    full_name:              PackageFullName,
+    display_name:           Option<String>,
+    package_root_folder:    Option<PathBuf>,
+    os_min_version:         Option<u64>,
+    os_max_version_tested:  Option<u64>,
+    supported_users:        Option<u32>,
+    package_sid:            Option<Sid>,
+    capability_sids:        Option<Vec<Sid>>,
+    raw_properties:         Vec<(WString, RawValue)>,
+}
+
+impl PackageInfo {
+    /// Load `pfn`'s properties from the live registry of this computer
+    pub fn load(pfn: &PackageFullName) -> io::Result<Self> { Self::load_in(&Win32Registry, pfn) }
+
+    /// Load `pfn`'s properties from `registry`
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use appx::registry::{MemoryRegistry, RawValue};
+    /// let reg = MemoryRegistry::new();
+    /// let pfn = appx::PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap();
+    /// reg.set_value(&format!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages\{}", pfn), "DisplayName", RawValue::sz("NcsiUwpApp"));
+    /// let info = appx::PackageInfo::load_in(&reg, &pfn).unwrap();
+    /// assert_eq!(info.display_name(), Some("NcsiUwpApp"));
+    /// assert_eq!(info.supported_users(), None);
+    /// ```
+    pub fn load_in(registry: &dyn RegistryBackend, pfn: &PackageFullName) -> io::Result<Self> {
+        Self::load_from(&reg::Key::open(registry, crate::repository::PACKAGES)?, pfn)
+    }
+
+    /// Load `pfn`'s properties from an already open `Repository\Packages` key
+    pub(crate) fn load_from(packages: &reg::Key, pfn: &PackageFullName) -> io::Result<Self> {
+        let raw_properties = packages.subkey(pfn.units())?.values()?;
+        let find = |name: &[u16]| raw_properties.iter().find(|(n, _)| ordinal::eq_ignore_case(n.units(), name)).map(|(_, v)| v);
+
+        let capability_count = find(wchar::wch!("CapabilityCount")).and_then(|v| reg::decode_dword(v).ok());
+        let capability_sids = find(wchar::wch!("CapabilitySids")).and_then(|v| reg::decode_bytes(v.clone()).ok()).and_then(|blob| crate::parse_capability_sids(&blob, capability_count).ok());
+
+        Ok(Self {
+            full_name:              pfn.clone(),
+            display_name:           find(wchar::wch!("DisplayName"))       .and_then(|v| reg::decode_string(v).ok()),
+            package_root_folder:    find(wchar::wch!("PackageRootFolder")) .and_then(|v| reg::decode_pathbuf(v).ok()),
+            os_min_version:         find(wchar::wch!("OSMinVersion"))      .and_then(|v| reg::decode_qword(v).ok()),
+            os_max_version_tested:  find(wchar::wch!("OSMaxVersionTested")).and_then(|v| reg::decode_qword(v).ok()),
+            supported_users:        find(wchar::wch!("SupportedUsers"))    .and_then(|v| reg::decode_dword(v).ok()),
+            package_sid:            find(wchar::wch!("PackageSid"))        .and_then(|v| reg::decode_bytes(v.clone()).ok()).and_then(|sid| Sid::from_bytes(&sid).ok()),
+            capability_sids,
+            raw_properties,
+        })
+    }
+
+    /// The package this is a snapshot of
+    pub fn full_name(&self) -> &PackageFullName { &self.full_name }
+
+    /// `DisplayName`
+    pub fn display_name(&self) -> Option<&str> { self.display_name.as_deref() }
+
+    /// `PackageRootFolder` (registry)
+    /// `InstallLocation` (powershell)
+    pub fn package_root_folder(&self) -> Option<&Path> { self.package_root_folder.as_deref() }
+
+    /// `PackageRootFolder` (registry)
+    /// `InstallLocation` (powershell)
+    pub fn install_location(&self) -> Option<&Path> { self.package_root_folder() }
+
+    /// `OSMinVersion`
+    pub fn os_min_version(&self) -> Option<u64> { self.os_min_version }
+
+    /// `OSMaxVersionTested`
+    pub fn os_max_version_tested(&self) -> Option<u64> { self.os_max_version_tested }
+
+    /// `SupportedUsers`
+    pub fn supported_users(&self) -> Option<u32> { self.supported_users }
+
+    /// `PackageSid`
+    pub fn package_sid(&self) -> Option<&Sid> { self.package_sid.as_ref() }
+
+    /// `CapabilitySids`, validated against `CapabilityCount` when present
+    pub fn capability_sids(&self) -> Option<&[Sid]> { self.capability_sids.as_deref() }
+
+    /// `CapabilitySids`, mapped to known [Capability]s where possible (unknown capabilities are [None])
+    pub fn capabilities(&self) -> impl Iterator<Item = (&Sid, Option<&'static Capability>)> {
+        self.capability_sids.iter().flatten().map(|sid| (sid, Capability::from_sid(sid)))
+    }
+
+    /// Every value under the package's key, as name/type/data triples in enumeration order
+    pub fn raw_properties(&self) -> &[(WString, RawValue)] { &self.raw_properties }
+}
+
+
+
+#[test] fn test_package_info() {
+    use crate::registry::MemoryRegistry;
+
+    let pfn = PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap();
+    let sid = pfn.family_name().appcontainer_sid();
+    let internet = Capability::find("internetClient").unwrap().sid();
+    let path = format!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages\{}", pfn);
+    let reg = MemoryRegistry::new();
+    reg.set_value(&path, "DisplayName",         RawValue::sz("NcsiUwpApp"));
+    reg.set_value(&path, "PackageRootFolder",   RawValue::sz(r"C:\WINDOWS\SystemApps\NcsiUwpApp_8wekyb3d8bbwe"));
+    reg.set_value(&path, "OSMinVersion",        RawValue::qword(0x000A_0000_0000_0000));
+    reg.set_value(&path, "SupportedUsers",      RawValue::sz("not a dword"));
+    reg.set_value(&path, "PackageSid",          RawValue::binary(sid.to_bytes()));
+    reg.set_value(&path, "CapabilityCount",     RawValue::dword(1));
+    reg.set_value(&path, "CapabilitySids",      RawValue::binary(internet.to_bytes()));
+    reg.set_value(&path, "Flags",               RawValue::dword(0));
+
+    let info = PackageInfo::load_in(&reg, &pfn).unwrap();
+    assert_eq!(info.full_name(),                &pfn);
+    assert_eq!(info.display_name(),             Some("NcsiUwpApp"));
+    assert_eq!(info.install_location(),         Some(Path::new(r"C:\WINDOWS\SystemApps\NcsiUwpApp_8wekyb3d8bbwe")));
+    assert_eq!(info.os_min_version(),           Some(0x000A_0000_0000_0000));
+    assert_eq!(info.os_max_version_tested(),    None);
+    assert_eq!(info.supported_users(),          None);
+    assert_eq!(info.package_sid(),              Some(&sid));
+    assert_eq!(info.capability_sids(),          Some(&[internet.clone()][..]));
+    assert_eq!(info.capabilities().map(|(_, cap)| cap.map(|c| c.name())).collect::<Vec<_>>(), [Some("internetClient")]);
+    assert_eq!(info.raw_properties().len(),     8);
+
+    assert_eq!(PackageInfo::load_in(&reg, &PackageFullName::new("Missing_1.0.0.0_neutral__8wekyb3d8bbwe").unwrap()).unwrap_err().kind(), io::ErrorKind::NotFound);
+}
@@ -0,0 +1,93 @@
+//! Ordinal, case insensitive comparisons of UTF16-ish [code units](https://unicode.org/glossary/#code_unit), ala
+//! [CompareStringOrdinal](https://docs.microsoft.com/en-us/windows/win32/api/stringapiset/nf-stringapiset-comparestringordinal)`(..., TRUE)`:
+//! each code unit is uppercased on its own, then code units are compared numerically.
+//!
+//! Implemented in pure Rust so it behaves the same on every platform.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+
+
+
+/// Uppercase a single code unit, approximating the Windows upcase table used by `RtlUpcaseUnicodeChar` and `CompareStringOrdinal`.
+///
+/// That table is a simple 1:1 mapping within the [BMP](https://unicode.org/glossary/#basic_multilingual_plane), so surrogates and
+/// characters without a 1:1 uppercase (e.g. `ß` → `SS`) are left as-is.  Beyond that, Windows never folds non-ASCII onto ASCII
+/// (`ı` and `ſ` stay put rather than becoming `I` and `S`), and its table predates the case pairs Unicode added for Cherokee (8.0),
+/// Cyrillic Extended-C (9.0) and Georgian Mtavruli (11.0) - so [char::to_uppercase]'s results are filtered to match.
+pub(crate) fn upcase(cu: u16) -> u16 {
+    if cu < 0x80 { return u16::from((cu as u8).to_ascii_uppercase()); }
+    let ch = match std::char::from_u32(u32::from(cu)) { Some(ch) => ch, None => return cu };
+    let mut upper = ch.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(u), None) => windows_case_pair(cu, u).unwrap_or(cu),
+        _               => cu,
+    }
+}
+
+/// Lowercase a single code unit, with the same limitations as [upcase]
+pub(crate) fn downcase(cu: u16) -> u16 {
+    if cu < 0x80 { return u16::from((cu as u8).to_ascii_lowercase()); }
+    let ch = match std::char::from_u32(u32::from(cu)) { Some(ch) => ch, None => return cu };
+    let mut lower = ch.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => windows_case_pair(cu, l).unwrap_or(cu),
+        _               => cu,
+    }
+}
+
+/// `Some(mapped)` if Windows' case tables also map the non-ASCII code unit `cu` to `mapped`
+fn windows_case_pair(cu: u16, mapped: char) -> Option<u16> {
+    let mapped = u16::try_from(u32::from(mapped)).ok()?;
+    let newer = |cu: u16| matches!(cu,
+        0x13A0 ..= 0x13FF | 0xAB70 ..= 0xABBF | // Cherokee, Cherokee Supplement (lowercase added in Unicode 8.0)
+        0x1C80 ..= 0x1C8F |                     // Cyrillic Extended-C (Unicode 9.0)
+        0x10A0 ..= 0x10FF | 0x1C90 ..= 0x1CBF   // Georgian, Georgian Extended (Mtavruli added in Unicode 11.0)
+    );
+    if mapped < 0x80 || (newer(cu) && newer(mapped)) { return None; }
+    Some(mapped)
+}
+
+pub(crate) fn eq_ignore_case(a: &[u16], b: &[u16]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(&a, &b)| a == b || upcase(a) == upcase(b))
+}
+
+pub(crate) fn cmp_ignore_case(a: &[u16], b: &[u16]) -> Ordering {
+    a.iter().map(|&cu| upcase(cu)).cmp(b.iter().map(|&cu| upcase(cu)))
+}
+
+pub(crate) fn hash_ignore_case<H: Hasher>(units: &[u16], state: &mut H) {
+    units.len().hash(state);
+    for &cu in units { upcase(cu).hash(state); }
+}
+
+
+
+#[test] fn test_ordinal() {
+    assert_eq!(upcase(u16::from(b'a')), u16::from(b'A'));
+    assert_eq!(upcase(u16::from(b'Z')), u16::from(b'Z'));
+    assert_eq!(upcase(u16::from(b'_')), u16::from(b'_'));
+    assert_eq!(upcase(0x00E9), 0x00C9); // é → É
+    assert_eq!(upcase(0x00DF), 0x00DF); // ß → SS isn't 1:1
+    assert_eq!(upcase(0xD83D), 0xD83D); // lone surrogate
+    assert_eq!(downcase(u16::from(b'A')), u16::from(b'a'));
+    assert_eq!(downcase(0x00C9), 0x00E9); // É → é
+    assert_eq!(downcase(0x0130), 0x0130); // İ → i̇ isn't 1:1
+
+    // where Windows' table differs from char::to_uppercase
+    assert_eq!(upcase(0x0131), 0x0131); // ı ↛ I
+    assert_eq!(upcase(0x017F), 0x017F); // ſ ↛ S
+    assert_eq!(downcase(0x212A), 0x212A); // K (Kelvin) ↛ k
+    assert_eq!(upcase(0x10D0), 0x10D0); // ა ↛ Ა (Mtavruli)
+    assert_eq!(upcase(0xAB70), 0xAB70); // ꭰ ↛ Ꭰ (Cherokee)
+    assert_eq!(upcase(0x00FF), 0x0178); // ÿ → Ÿ still folds outside of Latin-1
+    assert_eq!(upcase(0x03C3), 0x03A3); // σ → Σ
+    assert!(!eq_ignore_case(wchar::wch!("ıtem"), wchar::wch!("ITEM")));
+
+    assert!( eq_ignore_case(wchar::wch!("NcsiUwpApp_8wekyb3d8bbwe"), wchar::wch!("ncsiuwpapp_8WEKYB3D8BBWE")));
+    assert!(!eq_ignore_case(wchar::wch!("NcsiUwpApp"), wchar::wch!("NcsiUwpAp")));
+    assert_eq!(cmp_ignore_case(wchar::wch!("abc"), wchar::wch!("ABC")), Ordering::Equal);
+    assert_eq!(cmp_ignore_case(wchar::wch!("abc"), wchar::wch!("ABD")), Ordering::Less);
+    assert_eq!(cmp_ignore_case(wchar::wch!("a_"),  wchar::wch!("ab")),  Ordering::Greater); // "A_" vs "AB": '_' (0x5F) > 'B' (0x42)
+}
@@ -1,10 +1,17 @@
-use crate::reg;
-use crate::WString;
+use crate::{ordinal, reg, validate};
+use crate::registry::{RawValue, RegistryBackend, Win32Registry};
+use crate::{Capability, PackageFamilyName, PackageId, PackageIdError, PackageVersion, ProcessorArchitecture, ResourceId, Sid, WString};
 
+#[cfg(feature = "serde")] use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::cmp::Ordering;
+use std::convert::{TryFrom, TryInto};
 use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::io;
 use std::ops::Deref;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 
 
@@ -18,7 +25,7 @@ use std::path::PathBuf;
 /// | `Name`            | `NcsiUwpApp`          | `CanonicalGroupLimited.Ubuntu20.04onWindows`
 /// | `Version`         | `1000.19041.423.0`    | `2004.2020.812.0`
 /// | `Architecture`    | `neutral`             | `x64`
-/// | ???               | `neutral`             | (blank), `split.scale-100`
+/// | `ResourceId`      | `neutral`             | (blank), `split.scale-100`, `~`
 /// | `PublisherId`     | `8wekyb3d8bbwe`       | `79rhkp1fndgsc`
 ///
 /// ### Examples
@@ -34,19 +41,81 @@ use std::path::PathBuf;
 ///
 /// `powershell Get-AppxPackage ^| Format-Table -Property PackageFullName`<br>
 /// `HKCR\Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages\...`<br>
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)] pub struct PackageFullName(pub(crate) WString);
+///
+/// ### Validation
+///
+/// [new](Self::new), [FromStr] and [TryFrom] validate the name per the manifest rules, returning [PackageIdError] on failure.
+/// [new_unchecked](Self::new_unchecked) accepts anything (e.g. names enumerated from the registry).
+///
+/// ### Comparisons
+///
+/// Like Windows, [PartialEq], [Ord] and [Hash] ignore case (ordinally, ala `CompareStringOrdinal(..., TRUE)`).
+/// Compare the underlying [WString]s (`*a == *b`) for exact comparisons.
+///
+/// Ordered field by field, with `Version` compared numerically when it parses as a [PackageVersion]:
+/// `Foo_9.0.0.0_...` < `Foo_10.0.0.0_...`, so the greatest package of a family is its latest version.
+#[derive(Clone, Debug)] pub struct PackageFullName(pub(crate) WString);
 
 impl Deref              for PackageFullName { fn deref(&self) -> &Self::Target { &self.0 } type Target = WString; }
 impl Display            for PackageFullName { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { Display::fmt(&self.0.display(), fmt) } }
-impl From<&String  >    for PackageFullName { fn from(value: &String  ) -> Self { Self(WString::from(value)) } }
-impl From< String  >    for PackageFullName { fn from(value:  String  ) -> Self { Self(WString::from(value)) } }
-impl From<&str     >    for PackageFullName { fn from(value: &str     ) -> Self { Self(WString::from(value)) } }
-impl From<&   [u16]>    for PackageFullName { fn from(value: &   [u16]) -> Self { Self(WString::from(value)) } }
-impl From<&Vec<u16>>    for PackageFullName { fn from(value: &Vec<u16>) -> Self { Self(WString::from(value)) } }
-impl From< Vec<u16>>    for PackageFullName { fn from(value:  Vec<u16>) -> Self { Self(WString::from(value)) } }
+impl PartialEq          for PackageFullName { fn eq(&self, other: &Self) -> bool { ordinal::eq_ignore_case(self.0.units(), other.0.units()) } }
+impl Eq                 for PackageFullName {}
+impl Hash               for PackageFullName { fn hash<H: Hasher>(&self, state: &mut H) { ordinal::hash_ignore_case(self.0.units(), state) } }
+impl PartialOrd         for PackageFullName { fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) } }
+impl Ord                for PackageFullName {
+    fn cmp(&self, other: &Self) -> Ordering {
+        ordinal::cmp_ignore_case(self.name(), other.name())
+            .then_with(|| crate::version::cmp_units(self.version_units(), other.version_units()))
+            .then_with(|| ordinal::cmp_ignore_case(self.architecture_units(), other.architecture_units()))
+            .then_with(|| ordinal::cmp_ignore_case(self.resource_id_units(), other.resource_id_units()))
+            .then_with(|| ordinal::cmp_ignore_case(self.publisher_id(), other.publisher_id()))
+            .then_with(|| ordinal::cmp_ignore_case(self.0.units(), other.0.units()))
+    }
+}
+
+#[cfg(feature = "serde")] impl Serialize          for PackageFullName { fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> { serializer.collect_str(self) } }
+#[cfg(feature = "serde")] impl<'de> Deserialize<'de> for PackageFullName { fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> { String::deserialize(deserializer).map(Self::new_unchecked) } }
+impl FromStr            for PackageFullName { fn from_str(value: &str) -> Result<Self, Self::Err> { Self::checked(WString::from(value)) } type Err = PackageIdError; }
+impl TryFrom<&String  > for PackageFullName { fn try_from(value: &String  ) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom< String  > for PackageFullName { fn try_from(value:  String  ) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&str     > for PackageFullName { fn try_from(value: &str     ) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&   [u16]> for PackageFullName { fn try_from(value: &   [u16]) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&Vec<u16>> for PackageFullName { fn try_from(value: &Vec<u16>) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom< Vec<u16>> for PackageFullName { fn try_from(value:  Vec<u16>) -> Result<Self, Self::Error> { Self::checked(WString::from(value)) } type Error = PackageIdError; }
+impl TryFrom<&WString > for PackageFullName { fn try_from(value: &WString ) -> Result<Self, Self::Error> { Self::checked(value.clone())       } type Error = PackageIdError; }
+impl TryFrom< WString > for PackageFullName { fn try_from(value:  WString ) -> Result<Self, Self::Error> { Self::checked(value)               } type Error = PackageIdError; }
 
 impl PackageFullName {
-    pub fn new(pfn: impl Into<Self>) -> Self { pfn.into() }
+    /// Parse and validate a [PackageFullName]
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// assert!(appx::PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").is_ok());
+    /// assert!(appx::PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_8wekyb3d8bbwe").is_err());
+    /// ```
+    pub fn new(pfn: impl TryInto<Self, Error = PackageIdError>) -> Result<Self, PackageIdError> { pfn.try_into() }
+
+    /// Construct a [PackageFullName] **without** validating it (e.g. for names enumerated from the registry).
+    /// Accessors like [name](Self::name) return `&[]` for missing fields.
+    pub fn new_unchecked(pfn: impl Into<WString>) -> Self { Self(pfn.into()) }
+
+    fn checked(pfn: WString) -> Result<Self, PackageIdError> {
+        let pfn = Self(pfn);
+        pfn.validate()?;
+        Ok(pfn)
+    }
+
+    /// Check that this is a `Name_Version_Architecture_ResourceId_PublisherId` with valid fields
+    pub fn validate(&self) -> Result<(), PackageIdError> {
+        let fields = validate::fields(self.0.units(), 5)?;
+        validate::name(fields[0])?;
+        validate::version(fields[1])?;
+        validate::architecture(fields[2])?;
+        validate::resource_id(fields[3])?;
+        validate::publisher_id(fields[4])?;
+        Ok(())
+    }
 
     /// Package `Name`
     ///
@@ -56,31 +125,43 @@ impl PackageFullName {
     /// * `CanonicalGroupLimited.Ubuntu20.04onWindows`
     pub fn name(&self) -> &[u16] { self.field(0) }
 
-    /// Package `Version`
+    /// Package `Version`, or [None] if it isn't a valid [PackageVersion]
     ///
     /// ### Examples
     ///
     /// * `1000.19041.423.0`
     /// * `2004.2020.812.0`
-    pub fn version(&self) -> &[u16] { self.field(1) }
+    pub fn version(&self) -> Option<PackageVersion> { PackageVersion::from_units(self.version_units()).ok() }
 
-    /// Package `Architecture`
+    /// Package `Version` as raw [code units](https://unicode.org/glossary/#code_unit)
+    pub fn version_units(&self) -> &[u16] { self.field(1) }
+
+    /// Package `Architecture`, or [ProcessorArchitecture::Unknown] if unrecognized
     ///
     /// ### Examples
     ///
     /// * `x64`
     /// * `neutral`
-    pub fn architecture(&self) -> &[u16] { self.field(2) }
+    pub fn architecture(&self) -> ProcessorArchitecture { ProcessorArchitecture::from_units(self.architecture_units()).unwrap_or(ProcessorArchitecture::Unknown) }
+
+    /// Package `Architecture` as raw [code units](https://unicode.org/glossary/#code_unit)
+    pub fn architecture_units(&self) -> &[u16] { self.field(2) }
 
-    /// ???
+    /// Package `ResourceId`
     ///
     /// ### Examples
     ///
     /// * (blank)
-    /// * `neutral`         (`ResourceId`?)
-    /// * `split.scale-100` (**not** `ResourceId`)
-    #[allow(dead_code)] // TODO: name
-    fn field4(&self) -> &[u16] { self.field(3) }
+    /// * `neutral`
+    /// * `split.scale-100` (resource package)
+    /// * `~`               (bundle)
+    pub fn resource_id(&self) -> ResourceId { ResourceId::from_units(self.resource_id_units()) }
+
+    /// Package `ResourceId` as raw [code units](https://unicode.org/glossary/#code_unit)
+    pub fn resource_id_units(&self) -> &[u16] { self.field(3) }
+
+    /// Is this a resource package (`ResourceId` of `split.*`) rather than a main package or bundle?
+    pub fn is_resource_package(&self) -> bool { self.resource_id().is_resource_package() }
 
     /// Package `PublisherId`
     ///
@@ -90,60 +171,115 @@ impl PackageFullName {
     /// * `79rhkp1fndgsc`
     pub fn publisher_id(&self) -> &[u16] { self.field(4) }
 
-    fn field(&self, n: usize) -> &[u16] { self.0.units().splitn(5, |&cu| cu == u16::from(b'_')).skip(n).next().unwrap_or(&[]) }
+    /// Parse into a [PackageId] with typed components
+    pub fn id(&self) -> Result<PackageId, PackageIdError> { PackageId::try_from(self) }
+
+    /// The [PackageFamilyName] (`Name_PublisherId`) this package belongs to.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// let pfn = appx::PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap();
+    /// assert_eq!(pfn.family_name().to_string(), "NcsiUwpApp_8wekyb3d8bbwe");
+    /// ```
+    pub fn family_name(&self) -> PackageFamilyName {
+        let mut units = self.name().to_vec();
+        units.push(u16::from(b'_'));
+        units.extend_from_slice(self.publisher_id());
+        PackageFamilyName(WString::from(units))
+    }
+
+    fn field(&self, n: usize) -> &[u16] { self.0.units().splitn(5, |&cu| cu == u16::from(b'_')).nth(n).unwrap_or(&[]) }
 }
 
+/// Package properties read from `HKCR\Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages\...`.
+///
+/// Each `foo()` reads from the live registry of this computer, each `foo_in(registry)` from another [RegistryBackend].
 impl PackageFullName {
-    fn key_packages() -> io::Result<reg::Key> { reg::Key::hkcr(winstr0!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages"), reg::Options::NONE, reg::SAM::READ_ONLY) }
-
-    // `CapabilityCount` - parse w/ `CapabilitySids` instead?
-    // pub fn capability_count(&self) -> io::Result<u32> { ... }
-    
-    // `CapabilitySids` - format?
-    // pub fn capability_sids(&self) -> io::Result<Vec<u8>> { ... }
-    
+    /// `CapabilityCount`
+    pub fn capability_count(&self) -> io::Result<u32> { self.capability_count_in(&Win32Registry) }
+    pub fn capability_count_in(&self, registry: &dyn RegistryBackend) -> io::Result<u32> { self.key(registry)?.get_value_dword(None, Some(wchar::wch!("CapabilityCount"))) }
+
+    /// `CapabilitySids`, decoded with [parse_capability_sids](crate::parse_capability_sids) and validated against `CapabilityCount`
+    pub fn capability_sids(&self) -> io::Result<Vec<Sid>> { self.capability_sids_in(&Win32Registry) }
+    pub fn capability_sids_in(&self, registry: &dyn RegistryBackend) -> io::Result<Vec<Sid>> {
+        let key = self.key(registry)?;
+        let count = key.get_value_dword(None, Some(wchar::wch!("CapabilityCount")))?;
+        let blob = key.get_value_bytes(None, Some(wchar::wch!("CapabilitySids")))?;
+        crate::parse_capability_sids(&blob, Some(count)).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// `CapabilitySids`, mapped to known [Capability]s where possible (unknown capabilities are [None])
+    pub fn capabilities(&self) -> io::Result<Vec<(Sid, Option<&'static Capability>)>> { self.capabilities_in(&Win32Registry) }
+    pub fn capabilities_in(&self, registry: &dyn RegistryBackend) -> io::Result<Vec<(Sid, Option<&'static Capability>)>> {
+        Ok(self.capability_sids_in(registry)?.into_iter().map(|sid| { let cap = Capability::from_sid(&sid); (sid, cap) }).collect())
+    }
+
     /// `DisplayName`
-    pub fn display_name(&self) -> io::Result<String> { key_packages()?.get_value_string(Some(self.units0()), Some(winstr0!("DisplayName")), &mut temp_vec(64 * 1024)) }
+    pub fn display_name(&self) -> io::Result<String> { self.display_name_in(&Win32Registry) }
+    pub fn display_name_in(&self, registry: &dyn RegistryBackend) -> io::Result<String> { self.key(registry)?.get_value_string(None, Some(wchar::wch!("DisplayName"))) }
 
     /// `OSMaxVersionTested`
-    pub fn os_max_version_tested(&self) -> io::Result<u64> { key_packages()?.get_value_qword(Some(self.units0()), Some(winstr0!("OSMaxVersionTested"))) }
+    pub fn os_max_version_tested(&self) -> io::Result<u64> { self.os_max_version_tested_in(&Win32Registry) }
+    pub fn os_max_version_tested_in(&self, registry: &dyn RegistryBackend) -> io::Result<u64> { self.key(registry)?.get_value_qword(None, Some(wchar::wch!("OSMaxVersionTested"))) }
 
     /// `OSMinVersion`
-    pub fn os_min_version(&self) -> io::Result<u64> { key_packages()?.get_value_qword(Some(self.units0()), Some(winstr0!("OSMinVersion"))) }
+    pub fn os_min_version(&self) -> io::Result<u64> { self.os_min_version_in(&Win32Registry) }
+    pub fn os_min_version_in(&self, registry: &dyn RegistryBackend) -> io::Result<u64> { self.key(registry)?.get_value_qword(None, Some(wchar::wch!("OSMinVersion"))) }
 
     // `PackageId`
     // pub fn package_id(&self) -> io::Result<OsString> { ... } - is a reg key, but already implied from the PFN
 
     /// `PackageRootFolder` (registry)
     /// `InstallLocation` (powershell)
-    pub fn package_root_folder(&self) -> io::Result<PathBuf> { Self::key_packages()?.get_value_pathbuf(Some(self.units0()), Some(winstr0!("PackageRootFolder")), &mut temp_vec(64 * 1024)) }
+    pub fn package_root_folder(&self) -> io::Result<PathBuf> { self.package_root_folder_in(&Win32Registry) }
+    pub fn package_root_folder_in(&self, registry: &dyn RegistryBackend) -> io::Result<PathBuf> { self.key(registry)?.get_value_pathbuf(None, Some(wchar::wch!("PackageRootFolder"))) }
 
     /// `PackageRootFolder` (registry)
     /// `InstallLocation` (powershell)
     pub fn install_location(&self) -> io::Result<PathBuf> { self.package_root_folder() }
+    pub fn install_location_in(&self, registry: &dyn RegistryBackend) -> io::Result<PathBuf> { self.package_root_folder_in(registry) }
 
-    // `PackageSid` - don't have REG_BINARY APIs (yet)
-    //pub fn package_sid(&self) -> io::Result<Vec<u8>> {
-    //}
+    /// `PackageSid`
+    pub fn package_sid(&self) -> io::Result<Sid> { self.package_sid_in(&Win32Registry) }
+    pub fn package_sid_in(&self, registry: &dyn RegistryBackend) -> io::Result<Sid> {
+        let sid = self.key(registry)?.get_value_bytes(None, Some(wchar::wch!("PackageSid")))?;
+        Sid::from_bytes(&sid).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
 
-    /// `SupportedUsers`
-    pub fn supported_users(&self) -> io::Result<u32> { key_packages()?.get_value_dword(Some(self.units0()), Some(winstr0!("SupportedUsers"))) }
-}
+    /// Every value under this package's key (including undocumented ones like `Flags`), as name/type/data triples in enumeration order
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use appx::registry::{MemoryRegistry, RawValue};
+    /// let reg = MemoryRegistry::new();
+    /// let pfn = appx::PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap();
+    /// reg.set_value(&format!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages\{}", pfn), "Flags", RawValue::dword(0));
+    /// for (name, value) in pfn.raw_properties_in(&reg).unwrap() {
+    ///     println!("{} ({:?}) = {:?}", name.display(), value.ty, value.data);
+    /// }
+    /// ```
+    pub fn raw_properties(&self) -> io::Result<Vec<(WString, RawValue)>> { self.raw_properties_in(&Win32Registry) }
+    pub fn raw_properties_in(&self, registry: &dyn RegistryBackend) -> io::Result<Vec<(WString, RawValue)>> { self.key(registry)?.values() }
 
-fn key_packages() -> io::Result<reg::Key> { reg::Key::hkcr(winstr0!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages"), reg::Options::NONE, reg::SAM::READ_ONLY) }
+    /// `SupportedUsers`
+    pub fn supported_users(&self) -> io::Result<u32> { self.supported_users_in(&Win32Registry) }
+    pub fn supported_users_in(&self, registry: &dyn RegistryBackend) -> io::Result<u32> { self.key(registry)?.get_value_dword(None, Some(wchar::wch!("SupportedUsers"))) }
 
-fn temp_vec<T: Copy + Default>(n: usize) -> Vec<T> {
-    let mut v = Vec::new();
-    v.resize(n, Default::default());
-    v
+    fn key(&self, registry: &dyn RegistryBackend) -> io::Result<reg::Key> { reg::Key::open(registry, crate::repository::PACKAGES)?.subkey(self.units()) }
 }
 
+
 #[test] fn test_pfn() {
-    let pfn = PackageFullName::from("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe");
+    let pfn = PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap();
     assert_eq!(pfn.name(),          wchar::wch!("NcsiUwpApp"));
-    assert_eq!(pfn.version(),       wchar::wch!("1000.19041.423.0"));
-    assert_eq!(pfn.architecture(),  wchar::wch!("neutral"));
-    assert_eq!(pfn.field4(),        wchar::wch!("neutral"));
+    assert_eq!(pfn.version(),       Some(PackageVersion::new(1000, 19041, 423, 0)));
+    assert_eq!(pfn.version_units(), wchar::wch!("1000.19041.423.0"));
+    assert_eq!(pfn.architecture(),  ProcessorArchitecture::Neutral);
+    assert_eq!(pfn.architecture_units(), wchar::wch!("neutral"));
+    assert_eq!(pfn.resource_id(),   ResourceId::Neutral);
+    assert_eq!(pfn.resource_id_units(), wchar::wch!("neutral"));
     assert_eq!(pfn.publisher_id(),  wchar::wch!("8wekyb3d8bbwe"));
 
     if pfn.display_name().is_ok() { 
@@ -151,17 +287,66 @@ fn temp_vec<T: Copy + Default>(n: usize) -> Vec<T> {
         assert_eq!(pfn.package_root_folder().unwrap(),          PathBuf::from(r"C:\WINDOWS\SystemApps\NcsiUwpApp_8wekyb3d8bbwe"));
         assert_eq!(pfn.install_location().unwrap(),             PathBuf::from(r"C:\WINDOWS\SystemApps\NcsiUwpApp_8wekyb3d8bbwe"));
         assert!(   pfn.supported_users().unwrap_or(0)           >= 1);
+        assert_eq!(pfn.package_sid().unwrap(),                  pfn.family_name().appcontainer_sid());
+        assert_eq!(pfn.capability_sids().unwrap().len() as u32, pfn.capability_count().unwrap());
 
-        if std::env::var("COMPUTERNAME").map_or(false, |cn| cn == "SACRILEGE") {
+        if std::env::var("COMPUTERNAME").ok().as_deref() == Some("SACRILEGE") {
             assert!(pfn.os_max_version_tested().unwrap_or(0)    >= 0x03E8_4A61_01A7_0000);
             assert!(pfn.os_min_version().unwrap_or(0)           >= 0x000A_0000_0000_0000);
         }
     }
 
-    let pfn = PackageFullName::from("CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc");
+    let pfn = PackageFullName::new("CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc").unwrap();
     assert_eq!(pfn.name(),          wchar::wch!("CanonicalGroupLimited.UbuntuonWindows"));
-    assert_eq!(pfn.version(),       wchar::wch!("2004.2020.812.0"));
-    assert_eq!(pfn.architecture(),  wchar::wch!("x64"));
-    assert_eq!(pfn.field4(),        wchar::wch!(""));
+    assert_eq!(pfn.version(),       Some(PackageVersion::new(2004, 2020, 812, 0)));
+    assert_eq!(pfn.architecture(),  ProcessorArchitecture::X64);
+    assert_eq!(pfn.resource_id(),   ResourceId::None);
     assert_eq!(pfn.publisher_id(),  wchar::wch!("79rhkp1fndgsc"));
+    assert!(!pfn.is_resource_package());
+
+    let pfn = PackageFullName::new("CanonicalGroupLimited.Ubuntu20.04onWindows_2004.2020.812.0_neutral_split.scale-100_79rhkp1fndgsc").unwrap();
+    assert_eq!(pfn.resource_id(),   ResourceId::Split(vec![crate::ResourceQualifier::Scale(100)]));
+    assert!(pfn.is_resource_package());
+
+    let pfn = PackageFullName::new_unchecked("garbage");
+    assert_eq!(pfn.name(),          wchar::wch!("garbage"));
+    assert_eq!(pfn.publisher_id(),  &[] as &[u16]);
+    assert_eq!(pfn.version(),       None);
+    assert_eq!(pfn.validate(),      Err(PackageIdError::FieldCount { expected: 5, found: 1 }));
+}
+
+#[test] fn test_pfn_validate() {
+    use crate::PackageVersionError;
+    assert!("Microsoft.VCLibs.140.00_14.0.29231.0_x86__8wekyb3d8bbwe"          .parse::<PackageFullName>().is_ok());
+    assert!("Microsoft.WindowsStore_12010.1001.3.0_neutral_~_8wekyb3d8bbwe"     .parse::<PackageFullName>().is_ok());
+    assert_eq!("NcsiUwpApp_1000.19041.423.0_neutral_8wekyb3d8bbwe"              .parse::<PackageFullName>(), Err(PackageIdError::FieldCount { expected: 5, found: 4 }));
+    assert_eq!("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe_"     .parse::<PackageFullName>(), Err(PackageIdError::FieldCount { expected: 5, found: 6 }));
+    assert_eq!("Ncsi UwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe"     .parse::<PackageFullName>(), Err(PackageIdError::NameCharacter));
+    assert_eq!("NcsiUwpApp_1000.19041.423_neutral_neutral_8wekyb3d8bbwe"        .parse::<PackageFullName>(), Err(PackageIdError::Version(PackageVersionError::TooFewParts)));
+    assert!(matches!("NcsiUwpApp_1000.19041.423.0_x65_neutral_8wekyb3d8bbwe"    .parse::<PackageFullName>(), Err(PackageIdError::Architecture(_))));
+    assert!(matches!("NcsiUwpApp_1000.19041.423.0_unknown_neutral_8wekyb3d8bbwe".parse::<PackageFullName>(), Err(PackageIdError::Architecture(_))));
+    assert_eq!("NcsiUwpApp_1000.19041.423.0_neutral_neu tral_8wekyb3d8bbwe"     .parse::<PackageFullName>(), Err(PackageIdError::ResourceId));
+    assert!("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8WEKYB3D8BBWE".parse::<PackageFullName>().is_ok()); // publisher IDs ignore case
+}
+
+#[test] fn test_pfn_ord() {
+    let old = PackageFullName::new("Foo_9.0.0.0_x64__8wekyb3d8bbwe").unwrap();
+    let new = PackageFullName::new("Foo_10.0.0.0_x64__8wekyb3d8bbwe").unwrap();
+    assert!(old < new);
+    assert_eq!(vec![new.clone(), old.clone()].into_iter().max(), Some(new.clone()));
+
+    let bar = PackageFullName::new("Bar_10.0.0.0_x64__8wekyb3d8bbwe").unwrap();
+    let mut pfns = vec![new.clone(), bar.clone(), old.clone()];
+    pfns.sort();
+    assert_eq!(pfns, vec![bar, old, new]);
+
+    let a = PackageFullName::new_unchecked("Foo_1.0.0.0");
+    let b = PackageFullName::new_unchecked("Foo_1.0.0.0_");
+    assert_ne!(a.cmp(&b), Ordering::Equal);
+
+    let a = PackageFullName::new_unchecked("Foo_1.0.0.0_X64__8WEKYB3D8BBWE");
+    let b = PackageFullName::new("foo_1.0.0.0_x64__8wekyb3d8bbwe").unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a.cmp(&b), Ordering::Equal);
+    assert_eq!(vec![a, b].into_iter().collect::<std::collections::HashSet<_>>().len(), 1);
 }
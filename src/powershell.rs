@@ -0,0 +1,543 @@
+//! `powershell Get-AppxPackage`, `Add-AppxPackage`, `Remove-AppxPackage`, and `Get-AppxPackageManifest` with structured results
+//!
+//! Every cmdlet is run as `<exe> -NoLogo -NoProfile -NonInteractive -EncodedCommand ...` via a [CommandRunner], with results piped
+//! through `ConvertTo-Json` and parsed into typed structs.  Failures capture PowerShell's stderr into a [PowerShellError].
+//!
+//! The cmdlets with JSON results (`Get-AppxPackage`, `Get-AppxPackageManifest`) require the `serde` feature.
+//!
+//! `-EncodedCommand` (base64 of UTF-16LE) sidesteps command line quoting entirely, so the only escaping left is quoting arguments as
+//! single-quoted string literals within the script, and paths are resolved with `-LiteralPath` so `[`, `]`, `*` and `?` aren't wildcards.
+
+use crate::{AppxError, PackageFullName};
+#[cfg(feature = "serde")] use crate::{PackageFamilyName, PackageVersion};
+use crate::command::{CommandLine, CommandRunner, SystemRunner};
+
+#[cfg(feature = "serde")] use serde::{Deserialize, Deserializer};
+
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io;
+use std::path::Path;
+#[cfg(feature = "serde")] use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+
+
+/// Runs appx cmdlets via an external PowerShell executable.
+///
+/// ### Examples
+///
+/// ```rust
+/// # #[cfg(feature = "serde")] fn main() -> std::io::Result<()> {
+/// # if !cfg!(windows) { return Ok(()); }
+/// use appx::powershell::PowerShellBackend;
+/// for pkg in PowerShellBackend::new().get_appx_package("Microsoft.Windows*")? {
+///     println!("{} {} @ {:?}", pkg.name(), pkg.version(), pkg.install_location());
+/// }
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "serde"))] fn main() {}
+/// ```
+///
+/// ### Corresponds to
+///
+/// `powershell Get-AppxPackage ^| ConvertTo-Json`
+#[derive(Clone)]
+pub struct PowerShellBackend {
+    exe:        OsString,
+    runner:     Arc<dyn CommandRunner + Send + Sync>,
+    timeout:    Option<Duration>,
+}
+
+impl Debug   for PowerShellBackend { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { fmt.debug_struct("PowerShellBackend").field("exe", &self.exe).field("timeout", &self.timeout).finish_non_exhaustive() } }
+impl Default for PowerShellBackend { fn default() -> Self { Self::new() } }
+
+impl PowerShellBackend {
+    /// Run cmdlets via `powershell` (Windows PowerShell 5.1) with a [SystemRunner] and no timeout
+    pub fn new() -> Self { Self { exe: OsString::from("powershell"), runner: Arc::new(SystemRunner), timeout: None } }
+
+    /// Run cmdlets via `exe` instead (e.g. `pwsh`, `powershell.exe`, or an absolute path)
+    pub fn with_exe(self, exe: impl Into<OsString>) -> Self { Self { exe: exe.into(), ..self } }
+
+    /// Launch PowerShell via `runner` instead (e.g. a [MockRunner](crate::command::MockRunner) in tests)
+    pub fn with_runner(self, runner: impl CommandRunner + Send + Sync + 'static) -> Self { Self { runner: Arc::new(runner), ..self } }
+
+    /// Fail with [io::ErrorKind::TimedOut] if a cmdlet runs longer than `timeout`
+    pub fn with_timeout(self, timeout: impl Into<Option<Duration>>) -> Self { Self { timeout: timeout.into(), ..self } }
+
+    /// The PowerShell executable cmdlets are run with
+    pub fn exe(&self) -> &OsStr { &self.exe }
+
+    /// How long a cmdlet may run, if limited
+    pub fn timeout(&self) -> Option<Duration> { self.timeout }
+
+    /// `Get-AppxPackage -Name [name]` - get the installed packages whose name matches `name` (which may contain wildcards, e.g. `*`)
+    #[cfg(feature = "serde")]
+    pub fn get_appx_package(&self, name: &str) -> io::Result<Vec<AppxPackage>> {
+        let stdout = self.run("Get-AppxPackage", &format!(
+            "ConvertTo-Json -Compress -Depth 3 -InputObject @(Get-AppxPackage -Name {} | ForEach-Object {{ [ordered]@{{ \
+                Name = $_.Name; \
+                PackageFullName = $_.PackageFullName; \
+                PackageFamilyName = $_.PackageFamilyName; \
+                Version = [string]$_.Version; \
+                InstallLocation = $_.InstallLocation; \
+                Status = [string]$_.Status; \
+                Dependencies = @($_.Dependencies | ForEach-Object {{ $_.PackageFullName }}); \
+                IsFramework = $_.IsFramework; \
+                SignatureKind = [string]$_.SignatureKind \
+            }} }})",
+            quote(name),
+        ))?;
+        parse_json("Get-AppxPackage", &stdout)
+    }
+
+    /// `Add-AppxPackage -Path (Convert-Path -LiteralPath [path])` - install `path` as an appx package.
+    /// `path` is taken literally: wildcards and quotes are not special.
+    pub fn add_appx_package(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref().to_str().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Add-AppxPackage: `path` is not valid unicode"))?;
+        self.run("Add-AppxPackage", &format!("Add-AppxPackage -Path (Convert-Path -LiteralPath {})", quote(path)))?;
+        Ok(())
+    }
+
+    /// `Remove-AppxPackage -Package [pfn]` - uninstall `pfn` for the current user
+    pub fn remove_appx_package(&self, pfn: &PackageFullName) -> io::Result<()> {
+        self.run("Remove-AppxPackage", &format!("Remove-AppxPackage -Package {}", quote(&pfn.to_string())))?;
+        Ok(())
+    }
+
+    /// `(Get-AppxPackageManifest -Package [pfn]).OuterXml` - get the `AppxManifest.xml` of an installed package
+    #[cfg(feature = "serde")]
+    pub fn get_appx_package_manifest(&self, pfn: &PackageFullName) -> io::Result<String> {
+        let stdout = self.run("Get-AppxPackageManifest", &format!("ConvertTo-Json -Compress -InputObject (Get-AppxPackageManifest -Package {}).OuterXml", quote(&pfn.to_string())))?;
+        let xml : Option<String> = parse_json("Get-AppxPackageManifest", &stdout)?;
+        xml.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("Get-AppxPackageManifest: package {} not found", pfn)))
+    }
+
+    /// Run `script` and return stdout, or a [PowerShellError] if `script` failed
+    #[allow(clippy::io_other_error)] // io::Error::other requires rust 1.74
+    fn run(&self, cmdlet: &str, script: &str) -> io::Result<String> {
+        let script = format!("$ErrorActionPreference = 'Stop'; $ProgressPreference = 'SilentlyContinue'; [Console]::OutputEncoding = [Text.Encoding]::UTF8; {}", script);
+        let command = CommandLine::new(&self.exe).args(["-NoLogo", "-NoProfile", "-NonInteractive", "-EncodedCommand"]).arg(encode_command(&script)).timeout(self.timeout);
+        let output = self.runner.run(&command).map_err(|err| io::Error::new(err.kind(), format!("{}: `{}` failed: {}", cmdlet, self.exe.to_string_lossy(), err)))?;
+
+        if !output.is_success() {
+            return Err(io::Error::new(io::ErrorKind::Other, PowerShellError {
+                cmdlet:     cmdlet.into(),
+                exit_code:  output.code(),
+                stderr:     decode_stderr(&String::from_utf8_lossy(output.stderr())).trim().into(),
+            }));
+        }
+
+        let stdout = String::from_utf8_lossy(output.stdout());
+        Ok(stdout.trim_start_matches('\u{FEFF}').into())
+    }
+}
+
+/// Quote `s` as a single-quoted PowerShell string literal, within which only quotes are special (no `$` expansion or `` ` `` escapes).
+/// PowerShell also treats `‘` `’` `‚` `‛` as single quotes, so every kind of quote is escaped by doubling it.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('\'');
+    for ch in s.chars() {
+        if matches!(ch, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') { quoted.push(ch); }
+        quoted.push(ch);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Decode stderr that PowerShell serialized as CLIXML (`#< CLIXML` followed by `<Objs>`, which it does when stderr is redirected),
+/// keeping only the text of the error stream (`<S S="Error">`).  Plain text stderr is returned as-is.
+fn decode_stderr(stderr: &str) -> String {
+    let xml = match stderr.trim_start_matches('\u{FEFF}').trim_start().strip_prefix("#< CLIXML") {
+        Some(xml)   => xml,
+        None        => return stderr.into(),
+    };
+    let mut text = String::new();
+    for element in xml.split(r#"<S S="Error">"#).skip(1) {
+        text.push_str(&unescape_clixml(element.split("</S>").next().unwrap_or("")));
+    }
+    text
+}
+
+/// Undo XML's entity escapes, then CLIXML's `_xHHHH_` escapes of UTF-16 code units (e.g. `_x000D__x000A_` for `\r\n`)
+fn unescape_clixml(escaped: &str) -> String {
+    let xml = escaped.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&");
+    let mut units = Vec::with_capacity(xml.len());
+    let mut rest = xml.as_str();
+    while let Some(start) = rest.find("_x") {
+        units.extend(rest[..start].encode_utf16());
+        let after = &rest[start+2..];
+        match after.get(..5).filter(|e| e.ends_with('_') && e[..4].bytes().all(|b| b.is_ascii_hexdigit())) {
+            Some(e) => { units.push(u16::from_str_radix(&e[..4], 16).unwrap()); rest = &after[5..]; }
+            None    => { units.extend("_x".encode_utf16()); rest = after; }
+        }
+    }
+    units.extend(rest.encode_utf16());
+    String::from_utf16_lossy(&units)
+}
+
+/// Encode `script` for `-EncodedCommand`: base64 of UTF-16LE
+fn encode_command(script: &str) -> String {
+    const BASE64 : &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let bytes = script.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<u8>>();
+    let mut encoded = String::with_capacity(bytes.len() * 4 / 3 + 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+        for i in 0 .. 4 {
+            encoded.push(if i <= chunk.len() { char::from(BASE64[(n >> (18 - 6 * i)) as usize & 0x3F]) } else { '=' });
+        }
+    }
+    encoded
+}
+
+#[cfg(feature = "serde")]
+fn parse_json<T: for<'de> Deserialize<'de>>(cmdlet: &str, stdout: &str) -> io::Result<T> {
+    serde_json::from_str(stdout.trim()).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: unable to parse `ConvertTo-Json` output: {}", cmdlet, err)))
+}
+
+
+
+/// An installed package, as reported by `Get-AppxPackage`.
+///
+/// ### Corresponds to
+///
+/// `powershell Get-AppxPackage ^| Format-List`<br>
+/// [Microsoft.Windows.Appx.PackageManager.Commands.AppxPackage](https://docs.microsoft.com/en-us/powershell/module/appx/get-appxpackage)
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AppxPackage {
+    name:                   String,
+    package_full_name:      PackageFullName,
+    package_family_name:    PackageFamilyName,
+    version:                PackageVersion,
+    install_location:       Option<PathBuf>,
+    #[serde(deserialize_with = "deserialize_status")]
+    status:                 Vec<String>,
+    #[serde(default)]
+    dependencies:           Vec<PackageFullName>,
+    is_framework:           bool,
+    signature_kind:         SignatureKind,
+}
+
+#[cfg(feature = "serde")]
+impl AppxPackage {
+    /// `Name` (e.g. `NcsiUwpApp`)
+    pub fn name(&self) -> &str { &self.name }
+
+    /// `PackageFullName` (e.g. `NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe`)
+    pub fn full_name(&self) -> &PackageFullName { &self.package_full_name }
+
+    /// `PackageFamilyName` (e.g. `NcsiUwpApp_8wekyb3d8bbwe`)
+    pub fn family_name(&self) -> &PackageFamilyName { &self.package_family_name }
+
+    /// `Version` (e.g. `1000.19041.423.0`)
+    pub fn version(&self) -> PackageVersion { self.version }
+
+    /// `InstallLocation` (e.g. `C:\WINDOWS\SystemApps\NcsiUwpApp_8wekyb3d8bbwe`)
+    pub fn install_location(&self) -> Option<&Path> { self.install_location.as_deref() }
+
+    /// `Status` flags (e.g. `["Ok"]`, `["LicenseIssue", "Modified"]`)
+    pub fn status(&self) -> &[String] { &self.status }
+
+    /// `Status` is exactly `Ok`
+    pub fn is_ok(&self) -> bool { self.status.len() == 1 && self.status[0] == "Ok" }
+
+    /// `Dependencies` (e.g. the `Microsoft.VCLibs` framework packages this package uses)
+    pub fn dependencies(&self) -> &[PackageFullName] { &self.dependencies }
+
+    /// `IsFramework`
+    pub fn is_framework(&self) -> bool { self.is_framework }
+
+    /// `SignatureKind`
+    pub fn signature_kind(&self) -> &SignatureKind { &self.signature_kind }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_status<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let status = String::deserialize(deserializer)?;
+    Ok(status.split(',').map(str::trim).filter(|flag| !flag.is_empty()).map(String::from).collect())
+}
+
+
+
+/// How a package was signed (see `AppxPackage::signature_kind`).
+///
+/// ### Corresponds to
+///
+/// [Windows.ApplicationModel.PackageSignatureKind](https://docs.microsoft.com/en-us/uwp/api/windows.applicationmodel.packagesignaturekind)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize), serde(from = "String"))]
+#[non_exhaustive]
+pub enum SignatureKind {
+    /// Not signed (e.g. registered from a loose folder in developer mode)
+    None,
+    /// Signed with a developer certificate
+    Developer,
+    /// Signed with an enterprise certificate
+    Enterprise,
+    /// Signed by the Microsoft Store
+    Store,
+    /// Signed as part of Windows
+    System,
+    /// A `SignatureKind` this crate doesn't recognize
+    Other(String),
+}
+
+impl From<String> for SignatureKind {
+    fn from(kind: String) -> Self {
+        match kind.as_str() {
+            "None"          => SignatureKind::None,
+            "Developer"     => SignatureKind::Developer,
+            "Enterprise"    => SignatureKind::Enterprise,
+            "Store"         => SignatureKind::Store,
+            "System"        => SignatureKind::System,
+            _               => SignatureKind::Other(kind),
+        }
+    }
+}
+
+
+
+/// A PowerShell cmdlet failed.  Wrapped by the [io::Error]s returned from [PowerShellBackend].
+///
+/// ### Examples
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> {
+/// # if !cfg!(windows) { return Ok(()); }
+/// use appx::powershell::{PowerShellBackend, PowerShellError};
+/// if let Err(err) = PowerShellBackend::new().add_appx_package("missing.appx") {
+///     if let Some(err) = err.get_ref().and_then(|err| err.downcast_ref::<PowerShellError>()) {
+///         eprintln!("{} failed: {}", err.cmdlet(), err.stderr());
+///         if let Some(appx) = err.appx_error() {
+///             eprintln!("{}{}", appx.remediation(), if appx.is_retriable() { " (retriable)" } else { "" });
+///         }
+///         if let Some(id) = err.activity_id() {
+///             eprintln!("details: Get-AppPackageLog -ActivityID {}", id);
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PowerShellError {
+    cmdlet:     String,
+    exit_code:  Option<i32>,
+    stderr:     String,
+}
+
+impl PowerShellError {
+    /// The cmdlet that failed (e.g. `Add-AppxPackage`)
+    pub fn cmdlet(&self) -> &str { &self.cmdlet }
+
+    /// The exit code of the PowerShell process, or [None] if it was terminated by a signal
+    pub fn exit_code(&self) -> Option<i32> { self.exit_code }
+
+    /// Everything the PowerShell process wrote to stderr (typically an error record)
+    pub fn stderr(&self) -> &str { &self.stderr }
+
+    /// The HRESULT from the error record (e.g. `0x80073CF3` from `Deployment failed with HRESULT: 0x80073CF3, ...`), if any
+    pub fn hresult(&self) -> Option<u32> { crate::error::parse_hresult(&self.stderr) }
+
+    /// The decoded [hresult](Self::hresult), if any
+    pub fn appx_error(&self) -> Option<AppxError> { self.hresult().map(AppxError::from_hresult) }
+
+    /// The deployment's `[ActivityId]` from the error record, for `Get-AppPackageLog -ActivityID ...`, if any
+    pub fn activity_id(&self) -> Option<&str> { crate::error::parse_activity_id(&self.stderr) }
+}
+
+impl Error   for PowerShellError {}
+impl Display for PowerShellError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match (self.exit_code, self.appx_error().and_then(AppxError::name)) {
+            (Some(n), Some(name))   => write!(fmt, "{} failed (exit code {}, {})", self.cmdlet, n, name)?,
+            (Some(n), None)         => write!(fmt, "{} failed (exit code {})", self.cmdlet, n)?,
+            (None,    _)            => write!(fmt, "{} failed (signal)", self.cmdlet)?,
+        }
+        if !self.stderr.is_empty() { write!(fmt, ": {}", self.stderr)?; }
+        Ok(())
+    }
+}
+
+
+
+#[allow(clippy::io_other_error)] // io::Error::other requires rust 1.74
+#[cfg(unix)] #[test] fn test_powershell() {
+    use crate::command::{CommandOutput, MockRunner};
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("appx-test-powershell-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let exe = dir.join("pwsh");
+    std::fs::write(&exe, concat!(
+        "#!/bin/sh\n",
+        "printf '%s\\n' \"$@\" > \"$0.args\"\n",
+        "script=$(printf '%s' \"$5\" | base64 -d | iconv -f UTF-16LE -t UTF-8)\n",
+        "printf '%s' \"$script\" > \"$0.ps1\"\n",
+        "case \"$script\" in\n",
+        "    *Get-AppxPackageManifest*Missing*) echo null ;;\n",
+        "    *Get-AppxPackageManifest*) echo '\"<Package/>\"' ;;\n",
+        "    *Get-AppxPackage*) cat <<'EOF'\n",
+        "[{\"Name\":\"NcsiUwpApp\",\"PackageFullName\":\"NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe\",\"PackageFamilyName\":\"NcsiUwpApp_8wekyb3d8bbwe\",",
+        "\"Version\":\"1000.19041.423.0\",\"InstallLocation\":\"C:\\\\WINDOWS\\\\SystemApps\\\\NcsiUwpApp_8wekyb3d8bbwe\",\"Status\":\"Ok\",",
+        "\"Dependencies\":[\"Microsoft.VCLibs.140.00_14.0.29231.0_x64__8wekyb3d8bbwe\"],\"IsFramework\":false,\"SignatureKind\":\"System\"},",
+        "{\"Name\":\"Example\",\"PackageFullName\":\"Example_1.2.3.4_x64__8wekyb3d8bbwe\",\"PackageFamilyName\":\"Example_8wekyb3d8bbwe\",",
+        "\"Version\":\"1.2.3.4\",\"InstallLocation\":null,\"Status\":\"LicenseIssue, Modified\",\"Dependencies\":[],\"IsFramework\":true,\"SignatureKind\":\"Sideloaded\"}]\n",
+        "EOF\n",
+        "    ;;\n",
+        "    *Add-AppxPackage*Clixml*) printf '%s\\n' '#< CLIXML' '<Objs Version=\"1.1.0.1\" xmlns=\"http://schemas.microsoft.com/powershell/2004/04\">' \\\n",
+        "        '<Obj S=\"progress\" RefId=\"0\"><TN RefId=\"0\"><T>System.Management.Automation.PSCustomObject</T></TN><MS><I64 N=\"SourceId\">1</I64></MS></Obj>' \\\n",
+        "        '<S S=\"Error\">Add-AppxPackage : Deployment failed with HRESULT: 0x80073CF3, Package failed updates, dependency or conflict validation._x000D__x000A_</S>' \\\n",
+        "        '<S S=\"Error\">Windows cannot install package Clixml_1.0.0.0_x64__8wekyb3d8bbwe because it depends on &lt;Missing&gt; &amp; more_x005F_x000D_._x000D__x000A_</S>' \\\n",
+        "        '<S S=\"Error\">NOTE: For additional information, look for [ActivityId] 6a5b4c3d-2e1f-0001-a2b3-c4d5e6f7a8b9 in the Event Log_x000D__x000A_</S>' \\\n",
+        "        '</Objs>' >&2; exit 1 ;;\n",
+        "    *Remove-AppxPackage*) printf '%s\\n' 'Remove-AppxPackage : Deployment failed with HRESULT: 0x80073CF1, Package was not found.' \\\n",
+        "        'NOTE: For additional information, look for [ActivityId] 0b4e3f6c-2a1d-0002-9c8e-4f0b6c2a1d9c in the Event Log' >&2; exit 1 ;;\n",
+        "esac\n",
+    )).unwrap();
+    std::fs::set_permissions(&exe, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let args = || std::fs::read_to_string(dir.join("pwsh.args")).unwrap();
+    let script = || std::fs::read_to_string(dir.join("pwsh.ps1")).unwrap();
+    let ps = PowerShellBackend::new().with_exe(&exe);
+    assert_eq!(ps.exe(), exe.as_os_str());
+
+    ps.add_appx_package("example.appx").unwrap();
+    assert!(args().starts_with("-NoLogo\n-NoProfile\n-NonInteractive\n-EncodedCommand\n"));
+    assert!(script().starts_with("$ErrorActionPreference = 'Stop';"));
+    assert!(script().contains("Add-AppxPackage -Path (Convert-Path -LiteralPath 'example.appx')"));
+
+    #[cfg(feature = "serde")] {
+        let pkgs = ps.get_appx_package("*").unwrap();
+        assert!(script().contains("Get-AppxPackage -Name '*'"));
+        assert_eq!(pkgs.len(), 2);
+        assert_eq!(pkgs[0].name(),              "NcsiUwpApp");
+        assert_eq!(pkgs[0].full_name(),         &PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap());
+        assert_eq!(pkgs[0].family_name(),       &PackageFamilyName::new("NcsiUwpApp_8wekyb3d8bbwe").unwrap());
+        assert_eq!(pkgs[0].version(),           PackageVersion::new(1000, 19041, 423, 0));
+        assert_eq!(pkgs[0].install_location(),  Some(Path::new(r"C:\WINDOWS\SystemApps\NcsiUwpApp_8wekyb3d8bbwe")));
+        assert!(pkgs[0].is_ok());
+        assert_eq!(pkgs[0].dependencies(),      &[PackageFullName::new("Microsoft.VCLibs.140.00_14.0.29231.0_x64__8wekyb3d8bbwe").unwrap()]);
+        assert!(!pkgs[0].is_framework());
+        assert_eq!(pkgs[0].signature_kind(),    &SignatureKind::System);
+        assert_eq!(pkgs[1].install_location(),  None);
+        assert_eq!(pkgs[1].status(),            ["LicenseIssue", "Modified"]);
+        assert!(!pkgs[1].is_ok());
+        assert!(pkgs[1].is_framework());
+        assert_eq!(pkgs[1].signature_kind(),    &SignatureKind::Other("Sideloaded".into()));
+
+        ps.get_appx_package("O'Brien").unwrap();
+        assert!(script().contains("Get-AppxPackage -Name 'O''Brien'"));
+    }
+
+    // every path must survive -EncodedCommand + quoting verbatim, and reach -LiteralPath rather than a wildcard-expanding -Path
+    let unquote = |literal: &str| -> String {
+        let is_quote = |ch: char| matches!(ch, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}');
+        let mut chars = literal.chars().peekable();
+        assert!(matches!(chars.next(), Some(ch) if is_quote(ch)), "expected a single-quoted string: {}", literal);
+        let mut value = String::new();
+        while let Some(ch) = chars.next() {
+            if !is_quote(ch) { value.push(ch); continue; }
+            match chars.next() {
+                Some(next) if is_quote(next) => value.push(next),
+                Some(next) => panic!("unexpected {:?} after the closing quote of {}", next, literal),
+                None => return value,
+            }
+        }
+        panic!("unterminated string: {}", literal);
+    };
+    for path in [
+        r"C:\pkgs\app.appx",
+        r"C:\pkgs\[x64]\app.appx",
+        r"C:\pkgs\*.appx",
+        r"C:\pkgs\app?.appx",
+        r"C:\pkgs\[!a-z]*?.msix",
+        r"C:\pkgs\O'Brien's app.appx",
+        r"C:\pkgs\''.appx",
+        r"C:\pkgs\‘smart’ ‚low‛ quotes.appx",
+        r#"C:\pkgs\"double" “quotes”.appx"#,
+        r"C:\pkgs\$env:TEMP $(calc) @(1).appx",
+        r"C:\pkgs\back`tick`'.appx",
+        r"C:\pkgs\semi;colon & pipe | redirect > %PATH%.appx",
+        r"C:\Users\Jürgen\パッケージ\пакет 🦀.msix",
+        "C:\\pkgs\\tab\tand\u{3000}ideographic space.appx",
+        "-LiteralPath",
+        "",
+    ].iter() {
+        ps.add_appx_package(path).unwrap();
+        assert_eq!(args().lines().count(), 5, "{:?}", path);
+        assert!(args().lines().nth(4).unwrap().bytes().all(|b| b.is_ascii_alphanumeric() || b"+/=".contains(&b)));
+        let script = script();
+        let literal = script.split("Add-AppxPackage -Path (Convert-Path -LiteralPath ").nth(1).unwrap();
+        assert_eq!(unquote(literal.strip_suffix(')').unwrap()), *path);
+    }
+
+    let pfn = PackageFullName::new("Example_1.2.3.4_x64__8wekyb3d8bbwe").unwrap();
+    #[cfg(feature = "serde")] {
+        assert_eq!(ps.get_appx_package_manifest(&pfn).unwrap(), "<Package/>");
+        assert_eq!(ps.get_appx_package_manifest(&PackageFullName::new("Missing_1.2.3.4_x64__8wekyb3d8bbwe").unwrap()).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    let err = ps.remove_appx_package(&pfn).unwrap_err();
+    assert!(script().contains("Remove-AppxPackage -Package 'Example_1.2.3.4_x64__8wekyb3d8bbwe'"));
+    let err = err.get_ref().and_then(|err| err.downcast_ref::<PowerShellError>()).unwrap();
+    assert_eq!(err.cmdlet(),    "Remove-AppxPackage");
+    assert_eq!(err.exit_code(), Some(1));
+    assert!(err.stderr().starts_with("Remove-AppxPackage : Deployment failed with HRESULT: 0x80073CF1, Package was not found.\n"));
+    assert_eq!(err.hresult(),       Some(0x80073CF1));
+    assert_eq!(err.appx_error(),    Some(AppxError::InstallPackageNotFound));
+    assert_eq!(err.activity_id(),   Some("0b4e3f6c-2a1d-0002-9c8e-4f0b6c2a1d9c"));
+    assert!(err.to_string().starts_with("Remove-AppxPackage failed (exit code 1, ERROR_INSTALL_PACKAGE_NOT_FOUND): "));
+
+    // stderr redirected from a real PowerShell arrives as CLIXML
+    let err = ps.add_appx_package("Clixml.appx").unwrap_err();
+    let err = err.get_ref().and_then(|err| err.downcast_ref::<PowerShellError>()).unwrap();
+    assert_eq!(err.stderr(), "\
+        Add-AppxPackage : Deployment failed with HRESULT: 0x80073CF3, Package failed updates, dependency or conflict validation.\r\n\
+        Windows cannot install package Clixml_1.0.0.0_x64__8wekyb3d8bbwe because it depends on <Missing> & more_x000D_.\r\n\
+        NOTE: For additional information, look for [ActivityId] 6a5b4c3d-2e1f-0001-a2b3-c4d5e6f7a8b9 in the Event Log\
+    ");
+    assert_eq!(err.appx_error(),    Some(AppxError::InstallResolveDependencyFailed));
+    assert_eq!(err.activity_id(),   Some("6a5b4c3d-2e1f-0001-a2b3-c4d5e6f7a8b9"));
+    assert!(!err.to_string().contains("CLIXML"));
+
+    assert_eq!(PowerShellBackend::new().with_exe(dir.join("missing")).add_appx_package("example.appx").unwrap_err().kind(), io::ErrorKind::NotFound);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mock = MockRunner::new();
+    let ps = PowerShellBackend::new().with_exe("pwsh").with_runner(mock.clone()).with_timeout(Duration::from_secs(30));
+    #[cfg(feature = "serde")] {
+        mock.reply(CommandOutput::success("[]"));
+        mock.reply(CommandOutput::success("\u{FEFF}null\r\n"));
+        mock.reply(CommandOutput::success("not json"));
+        assert_eq!(ps.get_appx_package("Example").unwrap().len(), 0);
+        assert_eq!(ps.get_appx_package_manifest(&pfn).unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(ps.get_appx_package("Example").unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(mock.commands().len(), 3);
+    }
+    mock.reply_err(io::ErrorKind::TimedOut, "timed out");
+    assert_eq!(ps.add_appx_package("example.appx").unwrap_err().kind(), io::ErrorKind::TimedOut);
+    let commands = mock.commands();
+    let last = commands.last().unwrap();
+    assert_eq!(last.get_program(), "pwsh");
+    assert_eq!(last.get_timeout(), Some(Duration::from_secs(30)));
+    assert_eq!(last.get_args()[..4], ["-NoLogo", "-NoProfile", "-NonInteractive", "-EncodedCommand"]);
+    assert_eq!(last.get_args().len(), 5);
+
+    mock.reply(CommandOutput::failure(1, "Add-AppxPackage : Deployment failed with HRESULT: 0x80073D06, The package could not be installed because a higher version of this package is already installed."));
+    mock.reply(CommandOutput::failure(1, "Add-AppxPackage : Cannot find path 'C:\\missing.appx' because it does not exist."));
+    assert_eq!(AppxError::from_io_error(&ps.add_appx_package("example.appx").unwrap_err()), Some(AppxError::InstallPackageDowngrade));
+    assert_eq!(AppxError::from_io_error(&ps.add_appx_package("missing.appx").unwrap_err()), None);
+    assert_eq!(AppxError::from_io_error(&io::Error::new(io::ErrorKind::Other, "unrelated")), None);
+
+    assert_eq!(encode_command(""),      "");
+    assert_eq!(encode_command("a"),     "YQA=");
+    assert_eq!(encode_command("ab"),    "YQBiAA==");
+    assert_eq!(encode_command("abc"),   "YQBiAGMA");
+    assert_eq!(quote("it’s"),           "'it’’s'");
+}
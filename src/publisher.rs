@@ -0,0 +1,111 @@
+use sha2::{Digest, Sha256};
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+
+
+/// e.g. `8wekyb3d8bbwe` -
+/// A 13 character hash of a package's publisher, as found in [PackageFamilyName](crate::PackageFamilyName)s and [PackageFullName](crate::PackageFullName)s.
+///
+/// ### Examples
+///
+/// | Publisher                                                                             | PublisherId       |
+/// | ------------------------------------------------------------------------------------- | ----------------- |
+/// | `CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US`    | `8wekyb3d8bbwe`
+/// | `CN=23596F84-C3EA-4CD8-A7DF-550DCE37BCD0`                                             | `79rhkp1fndgsc`
+///
+/// ### Corresponds to
+///
+/// `powershell Get-AppxPackage ^| Format-Table -Property PublisherId`<br>
+/// [Identity@Publisher](https://docs.microsoft.com/en-us/uwp/schemas/appxpackage/appxmanifestschema/element-identity)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PublisherId([u8; 13]);
+
+/// [Crockford's Base32](https://www.crockford.com/base32.html) alphabet, lowercase
+const ALPHABET : &[u8; 32] = b"0123456789abcdefghjkmnpqrstvwxyz";
+
+impl PublisherId {
+    /// Hash a publisher distinguished name (e.g. `CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US`)
+    /// into a [PublisherId] (e.g. `8wekyb3d8bbwe`) the same way Windows does:
+    ///
+    /// * SHA-256 the UTF-16LE encoded publisher
+    /// * Take the first 64 bits of the hash
+    /// * Encode those (plus a trailing `0` bit) as 13 lowercase Crockford Base32 characters
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// let id = appx::PublisherId::from_publisher("CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US");
+    /// assert_eq!(id.as_str(), "8wekyb3d8bbwe");
+    /// ```
+    pub fn from_publisher(publisher: &str) -> Self {
+        let mut sha = Sha256::new();
+        for cu in publisher.encode_utf16() { sha.update(cu.to_le_bytes()); }
+        let hash = sha.finalize();
+
+        let mut bits = [0u8; 8];
+        bits.copy_from_slice(&hash[..8]);
+        let bits = u128::from(u64::from_be_bytes(bits)) << 1; // 64 bits + 1 padding bit = 65 bits = 13 x 5 bits
+
+        let mut id = [0u8; 13];
+        for (i, ch) in id.iter_mut().enumerate() {
+            *ch = ALPHABET[((bits >> (5 * (12 - i))) & 0x1F) as usize];
+        }
+        Self(id)
+    }
+
+    /// Parse [code units](https://unicode.org/glossary/#code_unit) of an existing [PublisherId] (e.g. `8wekyb3d8bbwe`), ignoring case.
+    /// Uppercase input (e.g. `8WEKYB3D8BBWE`) is normalized to lowercase.
+    pub fn from_units(units: &[u16]) -> Result<Self, PublisherIdError> {
+        if units.len() != 13 { return Err(PublisherIdError(())); }
+        let mut id = [0u8; 13];
+        for (dst, &cu) in id.iter_mut().zip(units.iter()) {
+            *dst = u8::try_from(cu).ok().map(|ch| ch.to_ascii_lowercase()).filter(|ch| ALPHABET.contains(ch)).ok_or(PublisherIdError(()))?;
+        }
+        Ok(Self(id))
+    }
+
+    /// The 13 character ID (e.g. `8wekyb3d8bbwe`)
+    pub fn as_str(&self) -> &str { std::str::from_utf8(&self.0[..]).unwrap_or_default() }
+
+    /// The 13 character ID as [code units](https://unicode.org/glossary/#code_unit)
+    pub fn to_units(&self) -> [u16; 13] {
+        let mut units = [0u16; 13];
+        for (dst, &ch) in units.iter_mut().zip(self.0.iter()) { *dst = u16::from(ch); }
+        units
+    }
+}
+
+impl Debug              for PublisherId { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { write!(fmt, "PublisherId({:?})", self.as_str()) } }
+impl Display            for PublisherId { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { fmt.write_str(self.as_str()) } }
+impl FromStr            for PublisherId { fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_units(&s.encode_utf16().collect::<Vec<u16>>()) } type Err = PublisherIdError; }
+impl TryFrom<&[u16]>    for PublisherId { fn try_from(units: &[u16]) -> Result<Self, Self::Error> { Self::from_units(units) } type Error = PublisherIdError; }
+impl PartialEq<[u16]>   for PublisherId { fn eq(&self, other: &[u16]) -> bool { self.to_units()[..] == *other } }
+impl PartialEq<str>     for PublisherId { fn eq(&self, other: &str) -> bool { self.as_str() == other } }
+
+
+
+/// A [PublisherId] wasn't 13 Crockford Base32 characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PublisherIdError(());
+
+impl Error   for PublisherIdError {}
+impl Display for PublisherIdError { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { fmt.write_str("publisher ID must be 13 base32 characters") } }
+
+
+
+#[test] fn test_publisher_id() {
+    assert_eq!(PublisherId::from_publisher("CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US").as_str(), "8wekyb3d8bbwe");
+    assert_eq!(PublisherId::from_publisher("CN=23596F84-C3EA-4CD8-A7DF-550DCE37BCD0").as_str(), "79rhkp1fndgsc");
+
+    let id : PublisherId = "8wekyb3d8bbwe".parse().unwrap();
+    assert_eq!(id.to_string(), "8wekyb3d8bbwe");
+    assert_eq!(id, wchar::wch!("8wekyb3d8bbwe")[..]);
+    assert!("8wekyb3d8bbw"  .parse::<PublisherId>().is_err()); // too short
+    assert!("8wekyb3d8bbwee".parse::<PublisherId>().is_err()); // too long
+    assert!("8wekyb3d8bbwu" .parse::<PublisherId>().is_err()); // `u` isn't in the alphabet
+    assert_eq!("8WEKYB3D8BBWE".parse::<PublisherId>().unwrap().as_str(), "8wekyb3d8bbwe"); // uppercase is normalized
+}
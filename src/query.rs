@@ -0,0 +1,203 @@
+use crate::{ordinal, repository};
+use crate::{PackageFullName, PackageVersion, ProcessorArchitecture, PublisherId};
+use crate::registry::{RegistryBackend, Win32Registry};
+
+use std::io;
+use std::ops::{Bound, RangeBounds};
+
+
+
+/// A filter over installed packages, evaluated lazily over the [repository] enumeration.
+///
+/// Every criteria that's been set must match.  Leaving everything unset matches every package.
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::*;
+/// let query = PackageQuery::new()
+///     .name("*Ubuntu*")
+///     .publisher_id("79rhkp1fndgsc".parse().unwrap())
+///     .architecture(ProcessorArchitecture::X64)
+///     .version(PackageVersion::new(2004, 0, 0, 0)..)
+///     .resource_packages(false)
+///     .latest_per_family(true);
+///
+/// assert!( query.matches(&PackageFullName::new("CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc").unwrap()));
+/// assert!(!query.matches(&PackageFullName::new("CanonicalGroupLimited.UbuntuonWindows_1804.2020.812.0_x64__79rhkp1fndgsc").unwrap()));
+///
+/// for pkg in query.run().into_iter().flatten() {
+///     println!("{}", pkg.unwrap());
+/// }
+/// ```
+///
+/// ### Corresponds to
+///
+/// `powershell Get-AppxPackage -Name *Ubuntu* -Publisher ...`
+#[derive(Clone, Debug)]
+pub struct PackageQuery {
+    name:               Option<Vec<u16>>,
+    publisher_id:       Option<PublisherId>,
+    architectures:      Vec<ProcessorArchitecture>,
+    version:            (Bound<PackageVersion>, Bound<PackageVersion>),
+    resource_packages:  Option<bool>,
+    latest_per_family:  bool,
+}
+
+impl Default for PackageQuery {
+    fn default() -> Self {
+        Self {
+            name:               None,
+            publisher_id:       None,
+            architectures:      Vec::new(),
+            version:            (Bound::Unbounded, Bound::Unbounded),
+            resource_packages:  None,
+            latest_per_family:  false,
+        }
+    }
+}
+
+impl PackageQuery {
+    /// A query matching every package
+    pub fn new() -> Self { Self::default() }
+
+    /// Only match packages whose `Name` matches `glob` (case insensitive, `*` matches any run of characters, `?` any single character)
+    pub fn name(self, glob: &str) -> Self { Self { name: Some(glob.encode_utf16().collect()), ..self } }
+
+    /// Only match packages with this `PublisherId`
+    pub fn publisher_id(self, publisher_id: PublisherId) -> Self { Self { publisher_id: Some(publisher_id), ..self } }
+
+    /// Only match packages with this `Architecture`.  Can be called multiple times to match any of several architectures.
+    pub fn architecture(mut self, architecture: ProcessorArchitecture) -> Self { self.architectures.push(architecture); self }
+
+    /// Only match packages with a `Version` in `range` (e.g. `PackageVersion::new(1,0,0,0)..PackageVersion::new(2,0,0,0)`)
+    pub fn version(self, range: impl RangeBounds<PackageVersion>) -> Self { Self { version: (range.start_bound().cloned(), range.end_bound().cloned()), ..self } }
+
+    /// Only match resource packages (`true`) or only match main/bundle/framework packages (`false`)
+    pub fn resource_packages(self, resource_packages: bool) -> Self { Self { resource_packages: Some(resource_packages), ..self } }
+
+    /// Only yield the latest matching package of each family (highest `Version`, preferring non-resource packages on ties)
+    pub fn latest_per_family(self, latest_per_family: bool) -> Self { Self { latest_per_family, ..self } }
+
+    /// Check if `pfn` matches this query (ignoring [latest_per_family](Self::latest_per_family), which depends on the other packages installed)
+    pub fn matches(&self, pfn: &PackageFullName) -> bool {
+        if !self.matches_family(pfn.name(), pfn.publisher_id()) { return false; }
+        if !self.architectures.is_empty() && !self.architectures.contains(&pfn.architecture()) { return false; }
+        if let Some(resource_packages) = self.resource_packages { if pfn.is_resource_package() != resource_packages { return false; } }
+        if self.version != (Bound::Unbounded, Bound::Unbounded) {
+            match pfn.version() {
+                Some(version)   => if !self.version.contains(&version) { return false; },
+                None            => return false,
+            }
+        }
+        true
+    }
+
+    /// Find matching packages installed on this computer
+    pub fn run(&self) -> io::Result<impl Iterator<Item = io::Result<PackageFullName>>> { self.run_in(&Win32Registry) }
+
+    /// Find matching packages listed in `registry`
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use appx::*;
+    /// let reg = registry::MemoryRegistry::new();
+    /// for pkg in &["Foo_1.0.0.0_x64__8wekyb3d8bbwe", "Foo_2.0.0.0_x64__8wekyb3d8bbwe", "Bar_1.0.0.0_x64__8wekyb3d8bbwe"] {
+    ///     let fam = PackageFullName::new(*pkg).unwrap().family_name();
+    ///     reg.create_key(&format!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Families\{}\{}", fam, pkg));
+    /// }
+    /// let latest = PackageQuery::new().name("f*").latest_per_family(true).run_in(&reg).unwrap().map(|p| p.unwrap().to_string()).collect::<Vec<_>>();
+    /// assert_eq!(latest, ["Foo_2.0.0.0_x64__8wekyb3d8bbwe"]);
+    /// ```
+    pub fn run_in<'r>(&self, registry: &'r dyn RegistryBackend) -> io::Result<impl Iterator<Item = io::Result<PackageFullName>> + 'r> {
+        let query = self.clone();
+        Ok(if self.latest_per_family {
+            Box::new(repository::families_in(registry)?.filter_map(move |fam| {
+                let fam = match fam { Ok(fam) => fam, Err(err) => return Some(Err(err)) };
+                if !query.matches_family(fam.name(), fam.publisher_id()) { return None; }
+                match repository::packages_for_family_in(registry, &fam) {
+                    Ok(packages)                                        => latest(packages.filter(|pfn| !matches!(pfn, Ok(pfn) if !query.matches(pfn)))).transpose(),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound   => None, // removed mid-iteration
+                    Err(err)                                            => Some(Err(err)),
+                }
+            })) as Box<dyn Iterator<Item = io::Result<PackageFullName>>>
+        } else {
+            Box::new(repository::packages_in(registry)?.filter(move |pfn| !matches!(pfn, Ok(pfn) if !query.matches(pfn))))
+        })
+    }
+
+    fn matches_family(&self, name: &[u16], publisher_id: &[u16]) -> bool {
+        if let Some(glob) = self.name.as_ref() { if !glob_ignore_case(glob, name) { return false; } }
+        if let Some(pid) = self.publisher_id.as_ref() { if !ordinal::eq_ignore_case(&pid.to_units(), publisher_id) { return false; } }
+        true
+    }
+}
+
+fn latest(packages: impl Iterator<Item = io::Result<PackageFullName>>) -> io::Result<Option<PackageFullName>> {
+    Ok(packages.collect::<io::Result<Vec<_>>>()?.into_iter().max_by(|a, b| (a.version(), !a.is_resource_package()).cmp(&(b.version(), !b.is_resource_package())).then_with(|| a.cmp(b))))
+}
+
+/// `*` matches any run of code units, `?` any single code unit, everything else matches ordinally ignoring case
+fn glob_ignore_case(glob: &[u16], text: &[u16]) -> bool {
+    let (star, question) = (u16::from(b'*'), u16::from(b'?'));
+    let (mut g, mut t) = (0, 0);
+    let mut backtrack = None; // (glob index after the last `*`, text index it's currently matching from)
+    while t < text.len() {
+        if g < glob.len() && glob[g] == star {
+            backtrack = Some((g + 1, t));
+            g += 1;
+        } else if g < glob.len() && (glob[g] == question || ordinal::upcase(glob[g]) == ordinal::upcase(text[t])) {
+            g += 1;
+            t += 1;
+        } else if let Some((bg, bt)) = backtrack {
+            backtrack = Some((bg, bt + 1));
+            g = bg;
+            t = bt + 1;
+        } else {
+            return false;
+        }
+    }
+    glob[g..].iter().all(|&cu| cu == star)
+}
+
+
+
+#[test] fn test_package_query() {
+    use crate::registry::MemoryRegistry;
+
+    assert!( glob_ignore_case(wchar::wch!("*ubuntu*"),  wchar::wch!("CanonicalGroupLimited.UbuntuonWindows")));
+    assert!( glob_ignore_case(wchar::wch!("Ncsi?wpApp"),wchar::wch!("NcsiUwpApp")));
+    assert!( glob_ignore_case(wchar::wch!("*"),         wchar::wch!("")));
+    assert!( glob_ignore_case(wchar::wch!("a*b*c"),     wchar::wch!("aXbYbZc")));
+    assert!(!glob_ignore_case(wchar::wch!("a*b*c"),     wchar::wch!("aXbYbZ")));
+    assert!(!glob_ignore_case(wchar::wch!("Ncsi"),      wchar::wch!("NcsiUwpApp")));
+
+    let reg = MemoryRegistry::new();
+    for pkg in &[
+        "NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe",
+        "CanonicalGroupLimited.UbuntuonWindows_1804.2020.812.0_x64__79rhkp1fndgsc",
+        "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc",
+        "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_neutral_split.scale-100_79rhkp1fndgsc",
+        "CanonicalGroupLimited.Ubuntu20.04onWindows_2004.2020.812.0_x64__79rhkp1fndgsc",
+    ] {
+        crate::repository::test_install(&reg, pkg);
+    }
+    let run = |query: PackageQuery| query.run_in(&reg).unwrap().map(|p| p.unwrap().to_string()).collect::<Vec<_>>();
+
+    assert_eq!(run(PackageQuery::new()).len(), 5);
+    assert_eq!(run(PackageQuery::new().name("ncsi*")), ["NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe"]);
+    assert_eq!(run(PackageQuery::new().publisher_id("79rhkp1fndgsc".parse().unwrap())).len(), 4);
+    assert_eq!(run(PackageQuery::new().architecture(ProcessorArchitecture::X64)).len(), 3);
+    assert_eq!(run(PackageQuery::new().architecture(ProcessorArchitecture::X64).architecture(ProcessorArchitecture::Neutral)).len(), 5);
+    assert_eq!(run(PackageQuery::new().resource_packages(true)), ["CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_neutral_split.scale-100_79rhkp1fndgsc"]);
+    assert_eq!(run(PackageQuery::new().resource_packages(false)).len(), 4);
+    assert_eq!(run(PackageQuery::new().version(PackageVersion::new(1804, 0, 0, 0)..PackageVersion::new(2004, 0, 0, 0))), ["CanonicalGroupLimited.UbuntuonWindows_1804.2020.812.0_x64__79rhkp1fndgsc"]);
+    assert_eq!(run(PackageQuery::new().version(..=PackageVersion::new(2004, 2020, 812, 0)).name("*Ubuntu*")).len(), 4);
+    assert_eq!(run(PackageQuery::new().name("*Ubuntu*").latest_per_family(true)), [
+        "CanonicalGroupLimited.Ubuntu20.04onWindows_2004.2020.812.0_x64__79rhkp1fndgsc",
+        "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc",
+    ]);
+    assert_eq!(run(PackageQuery::new().name("*Ubuntu*").resource_packages(true).latest_per_family(true)), ["CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_neutral_split.scale-100_79rhkp1fndgsc"]);
+    assert!(run(PackageQuery::new().name("Missing")).is_empty());
+}
@@ -1,9 +1,14 @@
 #![allow(dead_code)] // XXX
 
+use crate::WString;
+use crate::registry::{self, RawValue, RegistryBackend, RegistryKey, RegType, RegValue};
+
+
 pub(crate) struct NameBuffer(pub(crate) [u16; 255 + 1]); // https://docs.microsoft.com/en-us/windows/win32/sysinfo/registry-element-size-limits
 impl NameBuffer {
     pub fn len(&self) -> u32 { 255 + 1 }
 }
+#[allow(clippy::derivable_impls)] // [u16; 256] isn't Default
 impl Default for NameBuffer {
     fn default() -> Self {
         Self([ // 16 x 16 = 256 = 255 + 1
@@ -32,8 +37,8 @@ impl Default for NameBuffer {
 #[cfg(windows)] use winapi::um::winreg::*;
 #[cfg(windows)] use winapi::um::winnt::*;
 
-#[cfg(not(windows))] type HKEY      = *mut std::ffi::c_void;
-#[cfg(not(windows))] type REGSAM    = u32;
+#[cfg(not(windows))] #[allow(clippy::upper_case_acronyms)] type HKEY      = *mut std::ffi::c_void; // mirrors winapi
+#[cfg(not(windows))] #[allow(clippy::upper_case_acronyms)] type REGSAM    = u32;
 
 use std::convert::*;
 use std::ffi::OsString;
@@ -56,24 +61,27 @@ impl Options {
 
 /// [Registry Key Security and Access Rights](https://docs.microsoft.com/en-us/windows/win32/sysinfo/registry-key-security-and-access-rights)
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::upper_case_acronyms)] // mirrors REGSAM
 pub struct SAM(u32);
 impl SAM {
     pub const ALL_ACCESS : SAM = SAM(win0!(KEY_ALL_ACCESS));
     pub const READ_ONLY  : SAM = SAM(win0!(KEY_QUERY_VALUE | KEY_ENUMERATE_SUB_KEYS));
+    pub const NOTIFY     : SAM = SAM(win0!(KEY_NOTIFY));
     // TODO: saner read-only options
 }
 
 
 
-pub struct Key(HKEY);
+/// An owned win32 [HKEY]
+pub(crate) struct Hkey(HKEY);
 
-impl Key {
+impl Hkey {
     /// Takeover ownership of a give hkey
     ///
     /// ### SAFETY
     ///
-    /// * `hkey` must be a valid [HKEY] for the duration of [Key]'s lifetime (e.g. it must be defined behavior to pass it to [Reg*])
-    /// * `hkey` will be [RegCloseKey]ed when [Key] is [Drop]ped, so don't free it yourself (to avoid double-free bugs!)
+    /// * `hkey` must be a valid [HKEY] for the duration of [Hkey]'s lifetime (e.g. it must be defined behavior to pass it to [Reg*])
+    /// * `hkey` will be [RegCloseKey]ed when [Hkey] is [Drop]ped, so don't free it yourself (to avoid double-free bugs!)
     ///
     /// [RegCloseKey]:      https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regclosekey
     /// [Reg*]:             https://docs.microsoft.com/en-us/windows/win32/api/winreg/
@@ -109,30 +117,35 @@ impl Key {
         self.enum_key_w_impl(index, name)
     }
 
-    pub(crate) fn get_value_dword(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<u32> {
-        let mut r = [0u32];
-        self.get_value_w_impl(sub_key, value, win0!(RRF_RT_REG_DWORD), None, &mut r[..])?;
-        let [r] = r;
-        Ok(r)
-    }
-
-    pub(crate) fn get_value_qword(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<u64> {
-        let mut r = [0u64];
-        self.get_value_w_impl(sub_key, value, win0!(RRF_RT_REG_QWORD), None, &mut r[..])?;
-        let [r] = r;
-        Ok(r)
-    }
-
-    pub(crate) fn get_value_string(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>, buf: &mut [u16]) -> io::Result<String> {
-        self.get_value_w_impl(sub_key, value, win0!(RRF_RT_REG_SZ), None, buf).map(Self::trim_u16).map(String::from_utf16_lossy)
+    /// [RegEnumValueW], growing the data buffer as needed
+    ///
+    /// [RegEnumValueW]:    https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regenumvaluew
+    pub(crate) fn enum_value_w(&self, index: u32) -> io::Result<Option<(WString, RawValue)>> {
+        self.enum_value_w_impl(index)
     }
 
-    pub(crate) fn get_value_os_string(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>, buf: &mut [u16]) -> io::Result<OsString> {
-        self.get_value_w_impl(sub_key, value, win0!(RRF_RT_REG_SZ), None, buf).map(Self::trim_u16).map(Self::os_string_from_wide)
+    /// [RegGetValueW] without any type restrictions or expansion, growing the buffer as needed
+    ///
+    /// * `value` must be `'\0'`-terminated or this function will panic
+    ///
+    /// [RegGetValueW]:     https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-reggetvaluew
+    pub(crate) fn get_value_raw(&self, value: &[u16]) -> io::Result<RawValue> {
+        let mut ty = 0;
+        let mut data = Vec::new();
+        self.get_value_w_impl(None, Some(value), win0!(RRF_RT_ANY | RRF_NOEXPAND), Some(&mut ty), &mut data)?;
+        Ok(RawValue { ty: RegType(ty), data })
     }
 
-    pub(crate) fn get_value_pathbuf(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>, buf: &mut [u16]) -> io::Result<PathBuf> {
-        self.get_value_os_string(sub_key, value, buf).map(PathBuf::from)
+    /// [RegNotifyChangeKeyValue] - asynchronously signal `event` the next time a subkey is added/removed or a value is set, anywhere under this key.
+    /// The key must have been opened with [SAM::NOTIFY].  Re-register after each signal.
+    ///
+    /// ### SAFETY
+    ///
+    /// * `event` must be a valid event handle for as long as the registration is outstanding (until it's signaled or this key is closed)
+    ///
+    /// [RegNotifyChangeKeyValue]:  https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regnotifychangekeyvalue
+    pub(crate) unsafe fn notify_change_key_value(&self, event: *mut std::ffi::c_void) -> io::Result<()> {
+        self.notify_change_key_value_impl(event)
     }
 
     /// HKEY_CLASSES_ROOT
@@ -151,29 +164,210 @@ impl Key {
     pub(crate) fn hku (sub_key: &[u16], options: Options, sam_desired: SAM) -> io::Result<Self> { unsafe { Self::open(win0!(HKEY_USERS),           sub_key, options, sam_desired) } }
 }
 
+impl RegistryKey for Hkey {
+    fn subkey(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>> {
+        let path = path.iter().copied().chain(Some(0)).collect::<Vec<u16>>();
+        Ok(Box::new(Hkey::subkey(self, &path, Options::NONE, SAM::READ_ONLY)?))
+    }
+
+    fn enum_key(&self, index: u32) -> io::Result<Option<WString>> {
+        let mut name = NameBuffer::default();
+        Ok(self.enum_key_w(index, &mut name)?.map(WString::from))
+    }
+
+    fn enum_value(&self, index: u32) -> io::Result<Option<(WString, RawValue)>> {
+        self.enum_value_w(index)
+    }
+
+    fn value(&self, name: &[u16]) -> io::Result<RawValue> {
+        let name = name.iter().copied().chain(Some(0)).collect::<Vec<u16>>();
+        self.get_value_raw(&name)
+    }
+}
+
+
+
+/// A key opened from any [RegistryBackend], with typed value getters
+pub(crate) struct Key(Box<dyn RegistryKey>);
+
 impl Key {
-    fn trim_u16(v: &[u16]) -> &[u16] { if v.last() != Some(&0) { v } else { &v[..v.len()-1] } }
+    /// Open `path` (**not** `\0`-terminated) from `backend`
+    pub(crate) fn open(backend: &dyn RegistryBackend, path: &[u16]) -> io::Result<Self> { backend.open(path).map(Self) }
+
+    /// Open `path` (**not** `\0`-terminated) relative to this key
+    pub(crate) fn subkey(&self, path: &[u16]) -> io::Result<Self> { self.0.subkey(path).map(Self) }
+
+    /// Get the name of the `index`th subkey, or [None] if `index` is past the end
+    pub(crate) fn enum_key(&self, index: u32) -> io::Result<Option<WString>> { self.0.enum_key(index) }
+
+    /// Get the name, type and data of the `index`th value, or [None] if `index` is past the end
+    pub(crate) fn enum_value(&self, index: u32) -> io::Result<Option<(WString, RawValue)>> { self.0.enum_value(index) }
+
+    /// Get the names, types and data of every value of this key, in enumeration order
+    pub(crate) fn values(&self) -> io::Result<Vec<(WString, RawValue)>> {
+        let mut values = Vec::new();
+        while let Some(value) = self.enum_value(u32::try_from(values.len()).map_err(|_| invalid_data("too many registry values"))?)? { values.push(value); }
+        Ok(values)
+    }
+
+    /// Get a value of any type, decoded
+    pub(crate) fn get_value(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<RegValue> {
+        self.get_value_raw(sub_key, value).map(RegValue::from)
+    }
+
+    /// Get a value of any type, undecoded
+    pub(crate) fn get_value_raw(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<RawValue> {
+        let value = value.unwrap_or(&[]);
+        match sub_key {
+            Some(sub_key)   => self.0.subkey(sub_key)?.value(value),
+            None            => self.0.value(value),
+        }
+    }
+
+    pub(crate) fn get_value_dword(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<u32> {
+        decode_dword(&self.get_value_raw(sub_key, value)?)
+    }
+
+    pub(crate) fn get_value_qword(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<u64> {
+        decode_qword(&self.get_value_raw(sub_key, value)?)
+    }
+
+    /// Get a `REG_SZ` or `REG_EXPAND_SZ` value, expanding the latter (like [RegGetValueW] with `RRF_RT_REG_SZ`)
+    ///
+    /// [RegGetValueW]:     https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-reggetvaluew
+    pub(crate) fn get_value_units(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<Vec<u16>> {
+        self.get_value_expand_units(sub_key, value, true)
+    }
+
+    /// Get a `REG_SZ` or `REG_EXPAND_SZ` value, expanding `%VARIABLE%`s of the latter only if `expand` is set
+    pub(crate) fn get_value_expand_units(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>, expand: bool) -> io::Result<Vec<u16>> {
+        decode_units(&self.get_value_raw(sub_key, value)?, expand)
+    }
+
+    pub(crate) fn get_value_expand_string(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>, expand: bool) -> io::Result<String> {
+        self.get_value_expand_units(sub_key, value, expand).map(|units| String::from_utf16_lossy(&units))
+    }
+
+    /// Get a `REG_MULTI_SZ` value (stops at the first empty string, like the `\0\0` terminator)
+    pub(crate) fn get_value_multi_units(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<Vec<WString>> {
+        decode_multi_units(&self.get_value_raw(sub_key, value)?)
+    }
+
+    pub(crate) fn get_value_multi_string(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<Vec<String>> {
+        self.get_value_multi_units(sub_key, value).map(|ss| ss.iter().map(|s| String::from_utf16_lossy(s.units())).collect())
+    }
+
+    pub(crate) fn get_value_string(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<String> {
+        self.get_value_units(sub_key, value).map(|units| String::from_utf16_lossy(&units))
+    }
+
+    pub(crate) fn get_value_os_string(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<OsString> {
+        self.get_value_units(sub_key, value).map(|units| Hkey::os_string_from_wide(&units))
+    }
+
+    pub(crate) fn get_value_pathbuf(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<PathBuf> {
+        self.get_value_os_string(sub_key, value).map(PathBuf::from)
+    }
+
+    pub(crate) fn get_value_bytes(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>) -> io::Result<Vec<u8>> {
+        decode_bytes(self.get_value_raw(sub_key, value)?)
+    }
+}
+
+
+
+// Typed decoding of already-read values, shared by the [Key] getters and by bulk readers like [PackageInfo](crate::PackageInfo)
+
+pub(crate) fn decode_dword(v: &RawValue) -> io::Result<u32> {
+    if v.ty != RegType::DWORD { return Err(unexpected_type()); }
+    let v = <[u8; 4]>::try_from(&v.data[..]).map_err(|_| invalid_data("REG_DWORD value isn't 4 bytes"))?;
+    Ok(u32::from_le_bytes(v))
+}
+
+pub(crate) fn decode_qword(v: &RawValue) -> io::Result<u64> {
+    if v.ty != RegType::QWORD { return Err(unexpected_type()); }
+    let v = <[u8; 8]>::try_from(&v.data[..]).map_err(|_| invalid_data("REG_QWORD value isn't 8 bytes"))?;
+    Ok(u64::from_le_bytes(v))
+}
+
+/// `REG_SZ` or `REG_EXPAND_SZ`, expanding `%VARIABLE%`s of the latter only if `expand` is set
+pub(crate) fn decode_units(v: &RawValue, expand: bool) -> io::Result<Vec<u16>> {
+    let units = match v.ty {
+        RegType::SZ                 => registry::trim_nuls(registry::units(&v.data)),
+        RegType::EXPAND_SZ          => registry::trim_nuls(registry::units(&v.data)),
+        _                           => return Err(unexpected_type()),
+    };
+    Ok(if expand && v.ty == RegType::EXPAND_SZ { expand_environment_strings(&units, Hkey::env_var_units) } else { units })
+}
+
+pub(crate) fn decode_string(v: &RawValue) -> io::Result<String> { decode_units(v, true).map(|units| String::from_utf16_lossy(&units)) }
+pub(crate) fn decode_pathbuf(v: &RawValue) -> io::Result<PathBuf> { decode_units(v, true).map(|units| PathBuf::from(Hkey::os_string_from_wide(&units))) }
+
+pub(crate) fn decode_multi_units(v: &RawValue) -> io::Result<Vec<WString>> {
+    if v.ty != RegType::MULTI_SZ { return Err(unexpected_type()); }
+    Ok(registry::multi_sz(&registry::units(&v.data)))
+}
+
+pub(crate) fn decode_bytes(v: RawValue) -> io::Result<Vec<u8>> {
+    if v.ty != RegType::BINARY { return Err(unexpected_type()); }
+    Ok(v.data)
 }
 
-#[cfg(not(windows))] impl Key {
+fn unexpected_type() -> io::Error { invalid_data("registry value has an unexpected type") }
+fn invalid_data(msg: &'static str) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, msg) }
+
+/// Replace `%VARIABLE%`s with their values according to `lookup` (normally the process environment), ala [ExpandEnvironmentStringsW].
+/// Unset variables are left as-is.
+///
+/// [ExpandEnvironmentStringsW]:    https://docs.microsoft.com/en-us/windows/win32/api/processenv/nf-processenv-expandenvironmentstringsw
+pub(crate) fn expand_environment_strings(units: &[u16], lookup: impl Fn(&[u16]) -> Option<Vec<u16>>) -> Vec<u16> {
+    let pct = u16::from(b'%');
+    let mut out = Vec::with_capacity(units.len());
+    let mut rest = units;
+    while let Some(start) = rest.iter().position(|&cu| cu == pct) {
+        out.extend_from_slice(&rest[..start]);
+        let after = &rest[start+1..];
+        match after.iter().position(|&cu| cu == pct).filter(|&end| end > 0).and_then(|end| Some((end, lookup(&after[..end])?))) {
+            Some((end, var)) => { out.extend(var); rest = &after[end+1..]; }
+            None             => { out.push(pct);   rest = after; } // the closing `%` (if any) may open the next variable
+        }
+    }
+    out.extend_from_slice(rest);
+    out
+}
+
+#[cfg(not(windows))] impl Hkey {
     unsafe fn open_ex_w_impl(_hkey: HKEY, _sub_key: &[u16], _options: Options, _sam_desired: SAM) -> io::Result<Self> {
-        Err(io::Error::new(io::ErrorKind::Other, "registry not implemented on this platform"))
+        Err(io::Error::new(io::ErrorKind::Unsupported, "registry not implemented on this platform"))
     }
 
     fn enum_key_w_impl<'s>(&self, _index: u32, _name: &'s mut NameBuffer) -> io::Result<Option<&'s [u16]>> {
-        Err(io::Error::new(io::ErrorKind::Other, "registry not implemented on this platform"))
+        Err(io::Error::new(io::ErrorKind::Unsupported, "registry not implemented on this platform"))
+    }
+
+    fn enum_value_w_impl(&self, _index: u32) -> io::Result<Option<(WString, RawValue)>> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "registry not implemented on this platform"))
     }
 
-    fn get_value_w_impl<'v, T>(&self, _sub_key: Option<&[u16]>, _value: Option<&[u16]>, _flags: u32, _ty: Option<&mut u32>, _data: &'v mut [T]) -> io::Result<&'v [T]> {
-        Err(io::Error::new(io::ErrorKind::Other, "registry not implemented on this platform"))
+    unsafe fn notify_change_key_value_impl(&self, _event: *mut std::ffi::c_void) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "registry not implemented on this platform"))
+    }
+
+    fn get_value_w_impl(&self, _sub_key: Option<&[u16]>, _value: Option<&[u16]>, _flags: u32, _ty: Option<&mut u32>, _data: &mut Vec<u8>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "registry not implemented on this platform"))
     }
 
     fn os_string_from_wide(buf: &[u16]) -> OsString {
         OsString::from(String::from_utf16_lossy(buf))
     }
+
+    fn env_var_units(name: &[u16]) -> Option<Vec<u16>> {
+        let value = std::env::var_os(String::from_utf16(name).ok()?)?;
+        Some(value.to_string_lossy().encode_utf16().collect())
+    }
 }
 
-#[cfg(windows)] impl Key {
+#[cfg(windows)] impl Hkey {
     unsafe fn open_ex_w_impl(hkey: HKEY, sub_key: &[u16], options: Options, sam_desired: SAM) -> io::Result<Self> {
         assert!(sub_key.last() == Some(&0), "`sub_key` must be null terminated - use wchar::wch_c!(\"...\")!");
         let mut result = null_mut();
@@ -194,17 +388,54 @@ impl Key {
         }
     }
 
-    fn get_value_w_impl<'v, T>(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>, flags: u32, ty: Option<&mut u32>, data: &'v mut [T]) -> io::Result<&'v [T]> {
+    unsafe fn notify_change_key_value_impl(&self, event: *mut std::ffi::c_void) -> io::Result<()> {
+        let status = RegNotifyChangeKeyValue(self.0, 1, REG_NOTIFY_CHANGE_NAME | REG_NOTIFY_CHANGE_LAST_SET, event.cast(), 1);
+        match status as u32 {
+            ERROR_SUCCESS   => Ok(()),
+            _               => Err(io::Error::from_raw_os_error(status)),
+        }
+    }
+
+    #[allow(clippy::io_other_error)] // io::Error::other requires rust 1.74
+    fn enum_value_w_impl(&self, index: u32) -> io::Result<Option<(WString, RawValue)>> {
+        let mut name = vec![0u16; 16383 + 1]; // https://docs.microsoft.com/en-us/windows/win32/sysinfo/registry-element-size-limits
+        let mut data = Vec::<u8>::new();
+        loop {
+            let mut name_len = name.len() as u32;
+            let mut data_len = u32::try_from(data.len()).map_err(|_| io::Error::new(io::ErrorKind::Other, "RegEnumValueW cannot read that much data"))?;
+            let mut ty = 0;
+            let buf = if data.is_empty() { null_mut() } else { data.as_mut_ptr() };
+            let status = unsafe { RegEnumValueW(self.0, index, name.as_mut_ptr(), &mut name_len, null_mut(), &mut ty, buf, &mut data_len) };
+            match status as u32 {
+                ERROR_SUCCESS if buf.is_null() && data_len > 0 => data.resize(data_len as usize, 0), // size query
+                ERROR_SUCCESS       => {
+                    data.truncate(data_len as usize);
+                    return Ok(Some((WString::from(&name[..(name_len as usize)]), RawValue { ty: RegType(ty), data })));
+                },
+                ERROR_MORE_DATA     => { let grown = (data_len as usize).max(2 * data.len()); data.resize(grown, 0); }
+                ERROR_NO_MORE_ITEMS => return Ok(None),
+                _                   => return Err(io::Error::from_raw_os_error(status)),
+            }
+        }
+    }
+
+    /// Reads into `data`, resizing it to fit: the value may change size between calls, so keep retrying on `ERROR_MORE_DATA`
+    #[allow(clippy::io_other_error)] // io::Error::other requires rust 1.74
+    fn get_value_w_impl(&self, sub_key: Option<&[u16]>, value: Option<&[u16]>, flags: u32, ty: Option<&mut u32>, data: &mut Vec<u8>) -> io::Result<()> {
         let sub_key = sub_key.map_or(null_mut(), |sk| { assert!(sk.last() == Some(&0), "`sub_key` must be null terminated - use wchar::wch_c!(\"...\")!"); sk.as_ptr() as *mut _ });
         let value   = value  .map_or(null_mut(), |v | { assert!(v .last() == Some(&0),   "`value` must be null terminated - use wchar::wch_c!(\"...\")!"); v .as_ptr() as *mut _ });
-        let ty      = ty     .map_or(null_mut(), |ty| ty);
-
-        let mut len = u32::try_from(std::mem::size_of_val(data)).map_err(|_| io::Error::new(io::ErrorKind::Other, "RegGetValueW cannot read that much data"))?;
-        let status = unsafe { RegGetValueW(self.0, sub_key, value, flags, ty, data.as_mut_ptr().cast(), &mut len) };
-        match status as u32 {
-            ERROR_SUCCESS   => Ok(&data[..((len as usize)/std::mem::size_of::<T>())]),
-            // XXX: Grow `data` on ERROR_MORE_DATA?
-            _               => Err(io::Error::from_raw_os_error(status))
+        let ty      = ty     .map_or(null_mut(), |ty| ty as *mut _);
+
+        loop {
+            let mut len = u32::try_from(data.len()).map_err(|_| io::Error::new(io::ErrorKind::Other, "RegGetValueW cannot read that much data"))?;
+            let buf = if data.is_empty() { null_mut() } else { data.as_mut_ptr().cast() };
+            let status = unsafe { RegGetValueW(self.0, sub_key, value, flags, ty, buf, &mut len) };
+            match status as u32 {
+                ERROR_SUCCESS if buf.is_null() && len > 0   => data.resize(len as usize, 0), // size query
+                ERROR_SUCCESS                               => { data.truncate(len as usize); return Ok(()); }
+                ERROR_MORE_DATA                             => { let grown = (len as usize).max(2 * data.len()); data.resize(grown, 0); }
+                _                                           => return Err(io::Error::from_raw_os_error(status)),
+            }
         }
     }
 
@@ -212,9 +443,14 @@ impl Key {
         use std::os::windows::ffi::*;
         OsString::from_wide(buf)
     }
+
+    fn env_var_units(name: &[u16]) -> Option<Vec<u16>> {
+        use std::os::windows::ffi::*;
+        Some(std::env::var_os(OsString::from_wide(name))?.encode_wide().collect())
+    }
 }
 
-impl Drop for Key {
+impl Drop for Hkey {
     fn drop(&mut self) {
         #[cfg(windows)] {
             let status = unsafe { RegCloseKey(self.0) } as u32;
@@ -222,3 +458,51 @@ impl Drop for Key {
         }
     }
 }
+
+
+
+#[test] fn test_key() {
+    use crate::registry::MemoryRegistry;
+    use wchar::wch;
+
+    let reg = MemoryRegistry::new();
+    reg.set_value(r"Test", "sz",        RawValue::sz("foo"));
+    reg.set_value(r"Test", "expand",    RawValue::expand_sz(r"%APPX_TEST_REG_UNSET%\%%50%"));
+    reg.set_value(r"Test", "multi",     RawValue::multi_sz(&["a", "bc"]));
+    reg.set_value(r"Test", "binary",    RawValue::binary(vec![1, 2, 3]));
+    reg.set_value(r"Test", "big",       RawValue::binary(vec![0xAB; 100_000]));
+    let key = Key::open(&reg, wch!("Test")).unwrap();
+
+    assert_eq!(key.get_value_string(None, Some(wch!("sz"))).unwrap(), "foo");
+    assert_eq!(key.get_value_string(None, Some(wch!("expand"))).unwrap(), r"%APPX_TEST_REG_UNSET%\%%50%");
+    assert_eq!(key.get_value_expand_string(None, Some(wch!("expand")), false).unwrap(), r"%APPX_TEST_REG_UNSET%\%%50%");
+    assert_eq!(key.get_value_multi_string(None, Some(wch!("multi"))).unwrap(), ["a", "bc"]);
+    assert_eq!(key.get_value_bytes(None, Some(wch!("binary"))).unwrap(), [1, 2, 3]);
+    assert_eq!(key.get_value_bytes(None, Some(wch!("big"))).unwrap().len(), 100_000);
+    assert_eq!(key.get_value(None, Some(wch!("multi"))).unwrap(), RegValue::MultiSz(vec!["a".into(), "bc".into()]));
+    assert_eq!(key.get_value(None, Some(wch!("binary"))).unwrap(), RegValue::Binary(vec![1, 2, 3]));
+
+    assert_eq!(key.get_value_bytes(None, Some(wch!("sz"))).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert_eq!(key.get_value_string(None, Some(wch!("multi"))).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert!(key.get_value(None, Some(wch!("missing"))).is_err());
+
+    let names = key.values().unwrap().into_iter().map(|(name, _)| name.display().to_string()).collect::<Vec<_>>();
+    assert_eq!(names, ["sz", "expand", "multi", "binary", "big"]);
+}
+
+#[test] fn test_expand_environment_strings() {
+    use wchar::wch;
+    let env = |name: &[u16]| match String::from_utf16_lossy(name).as_str() {
+        "ROOT"  => Some(wch!(r"C:\Root").to_vec()),
+        "PCT"   => Some(wch!("%").to_vec()),
+        _       => None,
+    };
+    let expand = |s: &str| String::from_utf16_lossy(&expand_environment_strings(&s.encode_utf16().collect::<Vec<_>>(), env));
+    assert_eq!(expand(r"%ROOT%\a"),          r"C:\Root\a");
+    assert_eq!(expand(r"%ROOT%%ROOT%"),       r"C:\RootC:\Root");
+    assert_eq!(expand(r"%UNSET%\%ROOT%"),    r"%UNSET%\C:\Root");
+    assert_eq!(expand(r"%%ROOT%"),            r"%C:\Root");
+    assert_eq!(expand(r"100%"),               r"100%");
+    assert_eq!(expand(r"%PCT%ROOT%"),         r"%ROOT%"); // values aren't re-expanded
+    assert_eq!(expand(""),                    "");
+}
@@ -0,0 +1,396 @@
+//! Pluggable registry access, so [repository](crate::repository) queries can run against something other than the live Win32 registry.
+//!
+//! | Backend               | Reads from |
+//! | --------------------- | ---------- |
+//! | [Win32Registry]       | The live registry of this computer (`HKEY_CLASSES_ROOT`), via [RegOpenKeyExW] and friends
+//! | [MemoryRegistry]      | An in-memory tree populated from code - handy for unit tests on any platform
+//! | [HiveRegistry]        | An offline registry hive file (e.g. a `UsrClass.dat` copied off a dead or imaged machine)
+//! | [WineRegistry]        | The `system.reg` / `user.reg` text files of a [Wine](https://www.winehq.org/) prefix
+//!
+//! All paths are `\` separated, relative to `HKEY_CLASSES_ROOT`, and case insensitive.
+//!
+//! [RegOpenKeyExW]:    https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regopenkeyexw
+
+use crate::{ordinal, WString};
+
+use std::io;
+use std::sync::{Arc, RwLock};
+
+pub use crate::hive::HiveRegistry;
+pub use crate::wine::WineRegistry;
+
+
+
+/// A registry (or something shaped like one) that keys can be opened from
+pub trait RegistryBackend {
+    /// Open the key at `path` (`\` separated, relative to `HKEY_CLASSES_ROOT`, **not** `\0`-terminated)
+    fn open(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>>;
+}
+
+/// An open key of a [RegistryBackend]
+pub trait RegistryKey {
+    /// Open the key at `path` (`\` separated, relative to this key, **not** `\0`-terminated)
+    fn subkey(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>>;
+
+    /// Get the name of the `index`th subkey, or [None] if `index` is past the end
+    fn enum_key(&self, index: u32) -> io::Result<Option<WString>>;
+
+    /// Get the name, type and data of the `index`th value, or [None] if `index` is past the end
+    fn enum_value(&self, index: u32) -> io::Result<Option<(WString, RawValue)>>;
+
+    /// Get the type and data of the value `name` (`&[]` for the key's default value)
+    fn value(&self, name: &[u16]) -> io::Result<RawValue>;
+}
+
+
+
+/// A [registry value type](https://docs.microsoft.com/en-us/windows/win32/sysinfo/registry-value-types) (`REG_*`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RegType(pub u32);
+impl RegType {
+    pub const NONE                  : RegType = RegType(0);
+    pub const SZ                    : RegType = RegType(1);
+    pub const EXPAND_SZ             : RegType = RegType(2);
+    pub const BINARY                : RegType = RegType(3);
+    pub const DWORD                 : RegType = RegType(4);
+    pub const DWORD_BIG_ENDIAN      : RegType = RegType(5);
+    pub const LINK                  : RegType = RegType(6);
+    pub const MULTI_SZ              : RegType = RegType(7);
+    pub const QWORD                 : RegType = RegType(11);
+}
+
+/// The type and raw bytes of a registry value
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RawValue {
+    pub ty:     RegType,
+    pub data:   Vec<u8>,
+}
+
+impl RawValue {
+    /// A `REG_SZ` value (the data will be UTF-16LE with a `\0` terminator)
+    pub fn sz(s: &str) -> Self { Self { ty: RegType::SZ, data: s.encode_utf16().chain(Some(0)).flat_map(u16::to_le_bytes).collect() } }
+
+    /// A `REG_DWORD` value
+    pub fn dword(v: u32) -> Self { Self { ty: RegType::DWORD, data: v.to_le_bytes().to_vec() } }
+
+    /// A `REG_QWORD` value
+    pub fn qword(v: u64) -> Self { Self { ty: RegType::QWORD, data: v.to_le_bytes().to_vec() } }
+
+    /// A `REG_EXPAND_SZ` value (the data will be UTF-16LE with a `\0` terminator)
+    pub fn expand_sz(s: &str) -> Self { Self { ty: RegType::EXPAND_SZ, ..Self::sz(s) } }
+
+    /// A `REG_MULTI_SZ` value (the data will be UTF-16LE, each string `\0` terminated, with a final `\0` terminator)
+    pub fn multi_sz(ss: &[&str]) -> Self { Self { ty: RegType::MULTI_SZ, data: ss.iter().flat_map(|s| s.encode_utf16().chain(Some(0))).chain(Some(0)).flat_map(u16::to_le_bytes).collect() } }
+
+    /// A `REG_BINARY` value
+    pub fn binary(data: impl Into<Vec<u8>>) -> Self { Self { ty: RegType::BINARY, data: data.into() } }
+}
+
+/// A decoded registry value
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::registry::*;
+/// assert_eq!(RegValue::from(RawValue::dword(42)), RegValue::Dword(42));
+/// assert_eq!(RegValue::from(RawValue::sz("foo")), RegValue::Sz("foo".into()));
+/// assert_eq!(RawValue::from(RegValue::Qword(1)), RawValue::qword(1));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RegValue {
+    /// `REG_NONE`
+    None(Vec<u8>),
+    /// `REG_SZ`
+    Sz(WString),
+    /// `REG_EXPAND_SZ` (unexpanded, e.g. `%SystemRoot%\System32`)
+    ExpandSz(WString),
+    /// `REG_BINARY`
+    Binary(Vec<u8>),
+    /// `REG_DWORD`
+    Dword(u32),
+    /// `REG_DWORD_BIG_ENDIAN`
+    DwordBigEndian(u32),
+    /// `REG_LINK`
+    Link(WString),
+    /// `REG_MULTI_SZ`
+    MultiSz(Vec<WString>),
+    /// `REG_QWORD`
+    Qword(u64),
+    /// Any other type (e.g. `REG_RESOURCE_LIST`), or a `REG_DWORD`/`REG_QWORD` with the wrong amount of data
+    Other(RawValue),
+}
+
+impl RegValue {
+    /// The [RegType] of this value
+    pub fn ty(&self) -> RegType {
+        match self {
+            RegValue::None(_)           => RegType::NONE,
+            RegValue::Sz(_)             => RegType::SZ,
+            RegValue::ExpandSz(_)       => RegType::EXPAND_SZ,
+            RegValue::Binary(_)         => RegType::BINARY,
+            RegValue::Dword(_)          => RegType::DWORD,
+            RegValue::DwordBigEndian(_) => RegType::DWORD_BIG_ENDIAN,
+            RegValue::Link(_)           => RegType::LINK,
+            RegValue::MultiSz(_)        => RegType::MULTI_SZ,
+            RegValue::Qword(_)          => RegType::QWORD,
+            RegValue::Other(raw)        => raw.ty,
+        }
+    }
+}
+
+impl From<RawValue> for RegValue {
+    fn from(raw: RawValue) -> Self {
+        match raw.ty {
+            RegType::NONE               => RegValue::None(raw.data),
+            RegType::SZ                 => RegValue::Sz(WString::from(trim_nuls(units(&raw.data)))),
+            RegType::EXPAND_SZ          => RegValue::ExpandSz(WString::from(trim_nuls(units(&raw.data)))),
+            RegType::BINARY             => RegValue::Binary(raw.data),
+            RegType::DWORD              if raw.data.len() == 4 => RegValue::Dword(u32::from_le_bytes([raw.data[0], raw.data[1], raw.data[2], raw.data[3]])),
+            RegType::DWORD_BIG_ENDIAN   if raw.data.len() == 4 => RegValue::DwordBigEndian(u32::from_be_bytes([raw.data[0], raw.data[1], raw.data[2], raw.data[3]])),
+            RegType::LINK               => RegValue::Link(WString::from(units(&raw.data))),
+            RegType::MULTI_SZ           => RegValue::MultiSz(multi_sz(&units(&raw.data))),
+            RegType::QWORD              if raw.data.len() == 8 => RegValue::Qword(u64::from_le_bytes([raw.data[0], raw.data[1], raw.data[2], raw.data[3], raw.data[4], raw.data[5], raw.data[6], raw.data[7]])),
+            _                           => RegValue::Other(raw),
+        }
+    }
+}
+
+impl From<RegValue> for RawValue {
+    fn from(value: RegValue) -> Self {
+        let ty = value.ty();
+        let le = |units: &[u16]| units.iter().flat_map(|cu| cu.to_le_bytes()).collect::<Vec<u8>>();
+        let data = match value {
+            RegValue::None(data)            => data,
+            RegValue::Sz(s)                 => le(s.units0()),
+            RegValue::ExpandSz(s)           => le(s.units0()),
+            RegValue::Binary(data)          => data,
+            RegValue::Dword(v)              => v.to_le_bytes().to_vec(),
+            RegValue::DwordBigEndian(v)     => v.to_be_bytes().to_vec(),
+            RegValue::Link(s)               => le(s.units()),
+            RegValue::MultiSz(ss)           => ss.iter().flat_map(|s| le(s.units0())).chain(vec![0, 0]).collect(),
+            RegValue::Qword(v)              => v.to_le_bytes().to_vec(),
+            RegValue::Other(raw)            => return raw,
+        };
+        RawValue { ty, data }
+    }
+}
+
+/// UTF-16LE bytes → code units (a trailing odd byte is ignored)
+pub(crate) fn units(data: &[u8]) -> Vec<u16> { data.chunks_exact(2).map(|cu| u16::from_le_bytes([cu[0], cu[1]])).collect() }
+
+/// Strip any `\0` terminators
+pub(crate) fn trim_nuls(mut units: Vec<u16>) -> Vec<u16> { while units.last() == Some(&0) { units.pop(); } units }
+
+/// `a\0b\0\0` → `["a", "b"]`
+pub(crate) fn multi_sz(units: &[u16]) -> Vec<WString> {
+    units.split(|&cu| cu == 0).take_while(|s| !s.is_empty()).map(WString::from).collect()
+}
+
+
+
+/// The live registry of this computer, rooted at `HKEY_CLASSES_ROOT`.
+///
+/// On platforms other than windows, opening anything fails.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Win32Registry;
+
+impl RegistryBackend for Win32Registry {
+    fn open(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>> {
+        let path = path.iter().copied().chain(Some(0)).collect::<Vec<u16>>();
+        Ok(Box::new(crate::reg::Hkey::hkcr(&path, crate::reg::Options::NONE, crate::reg::SAM::READ_ONLY)?))
+    }
+}
+
+
+
+/// An in-memory registry tree, populated from code.
+///
+/// Clones share the same tree, so changes made through one clone are visible through the others (and through already open keys.)
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::registry::*;
+/// let reg = MemoryRegistry::new();
+/// reg.set_value(r"Local Settings\Foo", "Bar", RawValue::dword(42));
+///
+/// let key = reg.open(&r"local settings\FOO".encode_utf16().collect::<Vec<_>>()).unwrap();
+/// assert_eq!(key.value(&"Bar".encode_utf16().collect::<Vec<_>>()).unwrap(), RawValue::dword(42));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemoryRegistry(Arc<RwLock<MemoryNode>>);
+
+#[derive(Clone, Debug, Default)]
+struct MemoryNode {
+    subkeys:    Vec<(WString, MemoryNode)>, // sorted case insensitively, like RegEnumKeyExW
+    values:     Vec<(WString, RawValue)>,   // in insertion order
+}
+
+impl MemoryRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self { Self::default() }
+
+    /// Create the key at `path` (and any missing parents)
+    pub fn create_key(&self, path: &str) { self.create_key_units(&path.encode_utf16().collect::<Vec<u16>>()) }
+
+    pub(crate) fn create_key_units(&self, path: &[u16]) {
+        let mut root = self.0.write().unwrap();
+        root.create(&split_path(path));
+    }
+
+    /// Delete the key at `path` (and all its subkeys).  Returns `false` if it didn't exist.
+    pub fn delete_key(&self, path: &str) -> bool {
+        let path = path.encode_utf16().collect::<Vec<u16>>();
+        let mut path = split_path(&path);
+        let name = match path.pop() { Some(name) => name, None => return false };
+        let mut root = self.0.write().unwrap();
+        let parent = match root.find_mut(&path) { Some(parent) => parent, None => return false };
+        let before = parent.subkeys.len();
+        parent.subkeys.retain(|(n, _)| !ordinal::eq_ignore_case(n.units(), name));
+        parent.subkeys.len() != before
+    }
+
+    /// Set the value `name` of the key at `path` (creating the key if necessary)
+    pub fn set_value(&self, path: &str, name: &str, value: RawValue) {
+        self.set_value_units(&path.encode_utf16().collect::<Vec<u16>>(), &name.encode_utf16().collect::<Vec<u16>>(), value)
+    }
+
+    pub(crate) fn set_value_units(&self, path: &[u16], name: &[u16], value: RawValue) {
+        let name = WString::from(name);
+        let mut root = self.0.write().unwrap();
+        let key = root.create(&split_path(path));
+        match key.values.iter_mut().find(|(n, _)| ordinal::eq_ignore_case(n.units(), name.units())) {
+            Some(existing)  => existing.1 = value,
+            None            => key.values.push((name, value)),
+        }
+    }
+
+    /// Delete the value `name` of the key at `path`.  Returns `false` if it didn't exist.
+    pub fn delete_value(&self, path: &str, name: &str) -> bool {
+        let path = path.encode_utf16().collect::<Vec<u16>>();
+        let name = name.encode_utf16().collect::<Vec<u16>>();
+        let mut root = self.0.write().unwrap();
+        let key = match root.find_mut(&split_path(&path)) { Some(key) => key, None => return false };
+        let before = key.values.len();
+        key.values.retain(|(n, _)| !ordinal::eq_ignore_case(n.units(), &name));
+        key.values.len() != before
+    }
+}
+
+impl RegistryBackend for MemoryRegistry {
+    fn open(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>> {
+        let path = split_path(path).into_iter().map(WString::from).collect::<Vec<_>>();
+        let key = MemoryKey { root: self.0.clone(), path };
+        key.with_node(|_| ())?;
+        Ok(Box::new(key))
+    }
+}
+
+impl MemoryNode {
+    fn find(&self, path: &[impl AsRef<[u16]>]) -> Option<&MemoryNode> {
+        path.iter().try_fold(self, |node, name| node.subkeys.iter().find(|(n, _)| ordinal::eq_ignore_case(n.units(), name.as_ref())).map(|(_, sk)| sk))
+    }
+
+    fn find_mut(&mut self, path: &[&[u16]]) -> Option<&mut MemoryNode> {
+        path.iter().try_fold(self, |node, name| node.subkeys.iter_mut().find(|(n, _)| ordinal::eq_ignore_case(n.units(), name)).map(|(_, sk)| sk))
+    }
+
+    fn create(&mut self, path: &[&[u16]]) -> &mut MemoryNode {
+        path.iter().fold(self, |node, name| {
+            let index = match node.subkeys.binary_search_by(|(n, _)| ordinal::cmp_ignore_case(n.units(), name)) {
+                Ok(index)   => index,
+                Err(index)  => { node.subkeys.insert(index, (WString::from(*name), MemoryNode::default())); index },
+            };
+            &mut node.subkeys[index].1
+        })
+    }
+}
+
+struct MemoryKey {
+    root:   Arc<RwLock<MemoryNode>>,
+    path:   Vec<WString>,
+}
+
+impl MemoryKey {
+    fn with_node<R>(&self, f: impl FnOnce(&MemoryNode) -> R) -> io::Result<R> {
+        let root = self.root.read().unwrap();
+        let node = root.find(&self.path.iter().map(WString::units).collect::<Vec<_>>()).ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        Ok(f(node))
+    }
+}
+
+impl RegistryKey for MemoryKey {
+    fn subkey(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>> {
+        let mut full = self.path.clone();
+        full.extend(split_path(path).into_iter().map(WString::from));
+        let key = MemoryKey { root: self.root.clone(), path: full };
+        key.with_node(|_| ())?;
+        Ok(Box::new(key))
+    }
+
+    fn enum_key(&self, index: u32) -> io::Result<Option<WString>> {
+        self.with_node(|node| node.subkeys.get(index as usize).map(|(name, _)| name.clone()))
+    }
+
+    fn enum_value(&self, index: u32) -> io::Result<Option<(WString, RawValue)>> {
+        self.with_node(|node| node.values.get(index as usize).cloned())
+    }
+
+    fn value(&self, name: &[u16]) -> io::Result<RawValue> {
+        self.with_node(|node| node.values.iter().find(|(n, _)| ordinal::eq_ignore_case(n.units(), name)).map(|(_, v)| v.clone()))?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+}
+
+fn split_path(path: &[u16]) -> Vec<&[u16]> {
+    path.split(|&cu| cu == u16::from(b'\\')).filter(|name| !name.is_empty()).collect()
+}
+
+
+
+#[test] fn test_reg_value() {
+    for raw in [RawValue::sz("foo"), RawValue::expand_sz("%TEMP%"), RawValue::multi_sz(&["a", "bc"]), RawValue::multi_sz(&[]), RawValue::dword(1), RawValue::qword(2), RawValue::binary(vec![1, 2, 3])].iter() {
+        assert_eq!(&RawValue::from(RegValue::from(raw.clone())), raw);
+    }
+    assert_eq!(RegValue::from(RawValue::multi_sz(&["a", "bc"])),             RegValue::MultiSz(vec!["a".into(), "bc".into()]));
+    assert_eq!(RegValue::from(RawValue::expand_sz("%TEMP%")),                RegValue::ExpandSz("%TEMP%".into()));
+    assert_eq!(RegValue::from(RawValue { ty: RegType::DWORD, data: vec![1] }), RegValue::Other(RawValue { ty: RegType::DWORD, data: vec![1] }));
+    assert_eq!(RegValue::from(RawValue { ty: RegType::SZ, data: vec![b'a', 0] }), RegValue::Sz("a".into())); // missing terminator
+    assert_eq!(RegValue::from(RawValue { ty: RegType::DWORD_BIG_ENDIAN, data: vec![0, 0, 0, 1] }), RegValue::DwordBigEndian(1));
+    assert_eq!(RegValue::from(RawValue { ty: RegType(8), data: vec![] }).ty(), RegType(8));
+}
+
+#[test] fn test_memory_registry() {
+    let reg = MemoryRegistry::new();
+    reg.set_value(r"A\B\C", "Value", RawValue::sz("hello"));
+    reg.create_key(r"A\b2");
+    reg.create_key(r"A\a1");
+
+    let a = reg.open(wchar::wch!("a")).unwrap();
+    assert_eq!(a.enum_key(0).unwrap(), Some(WString::from("a1")));
+    assert_eq!(a.enum_key(1).unwrap(), Some(WString::from("B")));
+    assert_eq!(a.enum_key(2).unwrap(), Some(WString::from("b2")));
+    assert_eq!(a.enum_key(3).unwrap(), None);
+
+    let c = a.subkey(wchar::wch!(r"b\c")).unwrap();
+    assert_eq!(c.value(wchar::wch!("VALUE")).unwrap(), RawValue::sz("hello"));
+    assert_eq!(c.value(wchar::wch!("missing")).unwrap_err().kind(), io::ErrorKind::NotFound);
+    assert_eq!(reg.open(wchar::wch!("missing")).err().map(|e| e.kind()), Some(io::ErrorKind::NotFound));
+
+    // changes are visible through already open keys
+    reg.set_value(r"A\B\C", "Value", RawValue::dword(1));
+    assert_eq!(c.value(wchar::wch!("Value")).unwrap(), RawValue::dword(1));
+    assert!(reg.delete_value(r"A\B\C", "value"));
+    assert!(!reg.delete_value(r"A\B\C", "value"));
+    assert!(reg.delete_key(r"a\b"));
+    assert!(!reg.delete_key(r"a\b"));
+    assert_eq!(c.value(wchar::wch!("Value")).unwrap_err().kind(), io::ErrorKind::NotFound);
+    assert_eq!(a.enum_key(1).unwrap(), Some(WString::from("b2")));
+
+    reg.set_value(r"a", "First",  RawValue::dword(1));
+    reg.set_value(r"a", "Second", RawValue::sz("2"));
+    assert_eq!(a.enum_value(0).unwrap(), Some((WString::from("First"),  RawValue::dword(1))));
+    assert_eq!(a.enum_value(1).unwrap(), Some((WString::from("Second"), RawValue::sz("2"))));
+    assert_eq!(a.enum_value(2).unwrap(), None);
+}
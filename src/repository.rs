@@ -1,10 +1,15 @@
 //! `HKCR\Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository`<br>
 //! [Windows::Management::Deployment::PackageManager](https://docs.microsoft.com/en-us/uwp/api/windows.management.deployment.packagemanager?view=winrt-19041)
+//!
+//! Enumeration is fallible at two levels: opening the repository (e.g. [io::ErrorKind::Unsupported] when querying the live registry on
+//! non-Windows platforms), and reading each entry (e.g. access denied or a corrupt hive).  Iterators yield an [Err] for the entry that
+//! failed and then end, so "no packages" and "couldn't read the packages" can be told apart.
 
 // TODO: winrt alternatives to most of these APIs might be nice
 
-use crate::{PackageFamilyName, PackageFullName, WString};
+use crate::{PackageFamilyName, PackageFullName, PackageInfo, WString};
 use crate::reg;
+use crate::registry::{RegistryBackend, Win32Registry};
 
 use std::io;
 use std::path::Path;
@@ -16,134 +21,225 @@ use std::path::Path;
 /// ### Examples
 ///
 /// ```rust
-/// for fam in appx::repository::families().unwrap() {
-///     println!("{}", fam);
+/// # fn main() -> std::io::Result<()> {
+/// # if !cfg!(windows) { return Ok(()); }
+/// for fam in appx::repository::families()? {
+///     println!("{}", fam?);
 /// }
+/// # Ok(())
+/// # }
 /// ```
-pub fn families() -> io::Result<impl Iterator<Item = PackageFamilyName>> { imp::families() }
+pub fn families() -> io::Result<impl Iterator<Item = io::Result<PackageFamilyName>>> { families_in(&Win32Registry) }
 
 /// Get the [PackageFullName]s installed on this computer
 ///
 /// ### Examples
 ///
 /// ```rust
-/// for pkg in appx::repository::packages().unwrap() {
-///     println!("{}", pkg);
+/// # fn main() -> std::io::Result<()> {
+/// # if !cfg!(windows) { return Ok(()); }
+/// for pkg in appx::repository::packages()? {
+///     println!("{}", pkg?);
 /// }
+/// # Ok(())
+/// # }
 /// ```
-pub fn packages() -> io::Result<impl Iterator<Item = PackageFullName>> { imp::packages() }
+pub fn packages() -> io::Result<impl Iterator<Item = io::Result<PackageFullName>>> { packages_in(&Win32Registry) }
 
 /// Get the [PackageFullName]s installed on this computer for a given [PackageFamilyName]
 ///
 /// ### Examples
 ///
 /// ```rust
-/// let fam = appx::PackageFamilyName::new("NcsiUwpApp_8wekyb3d8bbwe");
+/// let fam = appx::PackageFamilyName::new("NcsiUwpApp_8wekyb3d8bbwe").unwrap();
 /// if let Ok(packages) = appx::repository::packages_for_family(&fam) {
 ///     for pkg in packages {
-///         println!("{}", pkg);
+///         println!("{}", pkg.unwrap());
 ///     }
 /// }
 /// ```
-pub fn packages_for_family(family: &PackageFamilyName) -> io::Result<impl Iterator<Item = PackageFullName>> { imp::packages_for_family(family) }
+pub fn packages_for_family(family: &PackageFamilyName) -> io::Result<impl Iterator<Item = io::Result<PackageFullName>>> { packages_for_family_in(&Win32Registry, family) }
 
+/// Get a [PackageInfo] for every package installed on this computer, loaded lazily as the iterator advances
+///
+/// ### Examples
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> {
+/// # if !cfg!(windows) { return Ok(()); }
+/// for info in appx::repository::package_infos()? {
+///     let info = info?;
+///     println!("{} {:?}", info.full_name(), info.display_name());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn package_infos() -> io::Result<impl Iterator<Item = io::Result<PackageInfo>>> { package_infos_in(&Win32Registry) }
 
 /// Check if the [PackageFamilyName] appears on this computer
 pub fn has_family(fam: &PackageFamilyName) -> bool {
     if !cfg!(windows) { return false; }
-    let key = reg::Key::hkcr(winstr0!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Families"), reg::Options::NONE, reg::SAM::READ_ONLY);
-    key.ok().and_then(|key| key.subkey(fam.units0(), reg::Options::NONE, reg::SAM::READ_ONLY).ok()).is_some()
+    has_family_in(&Win32Registry, fam)
 }
 
 /// Check if the [PackageFullName] appears on this computer
 pub fn has_package(pfn: &PackageFullName) -> bool {
     if !cfg!(windows) { return false; }
-    let key = reg::Key::hkcr(winstr0!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages"), reg::Options::NONE, reg::SAM::READ_ONLY);
-    key.ok().and_then(|key| key.subkey(pfn.units0(), reg::Options::NONE, reg::SAM::READ_ONLY).ok()).is_some()
+    has_package_in(&Win32Registry, pfn)
+}
+
+
+
+/// Get the [PackageFamilyName]s listed in `registry`
+///
+/// ### Examples
+///
+/// ```rust
+/// use appx::registry::MemoryRegistry;
+/// let reg = MemoryRegistry::new();
+/// reg.create_key(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Families\NcsiUwpApp_8wekyb3d8bbwe");
+/// let families = appx::repository::families_in(&reg).unwrap().map(|f| f.unwrap().to_string()).collect::<Vec<_>>();
+/// assert_eq!(families, ["NcsiUwpApp_8wekyb3d8bbwe"]);
+/// ```
+pub fn families_in(registry: &dyn RegistryBackend) -> io::Result<impl Iterator<Item = io::Result<PackageFamilyName>>> {
+    Ok(SubkeysIter::new(reg::Key::open(registry, FAMILIES)?).map(|fam| fam.map(PackageFamilyName)))
+}
+
+/// Get the [PackageFullName]s listed in `registry`
+pub fn packages_in(registry: &dyn RegistryBackend) -> io::Result<impl Iterator<Item = io::Result<PackageFullName>>> {
+    Ok(SubkeysIter::new(reg::Key::open(registry, PACKAGES)?).map(|pfn| pfn.map(PackageFullName)))
+}
+
+/// Get the [PackageFullName]s listed in `registry` for a given [PackageFamilyName]
+pub fn packages_for_family_in(registry: &dyn RegistryBackend, family: &PackageFamilyName) -> io::Result<impl Iterator<Item = io::Result<PackageFullName>>> {
+    Ok(SubkeysIter::new(reg::Key::open(registry, FAMILIES)?.subkey(family.units())?).map(|pfn| pfn.map(PackageFullName)))
+}
+
+/// Get a [PackageInfo] for every package listed in `registry`, loaded lazily as the iterator advances
+pub fn package_infos_in(registry: &dyn RegistryBackend) -> io::Result<impl Iterator<Item = io::Result<PackageInfo>>> {
+    Ok(PackageInfosIter(SubkeysIter::new(reg::Key::open(registry, PACKAGES)?)))
+}
+
+/// Check if the [PackageFamilyName] appears in `registry`
+pub fn has_family_in(registry: &dyn RegistryBackend, fam: &PackageFamilyName) -> bool {
+    reg::Key::open(registry, FAMILIES).and_then(|key| key.subkey(fam.units())).is_ok()
+}
+
+/// Check if the [PackageFullName] appears in `registry`
+pub fn has_package_in(registry: &dyn RegistryBackend, pfn: &PackageFullName) -> bool {
+    reg::Key::open(registry, PACKAGES).and_then(|key| key.subkey(pfn.units())).is_ok()
 }
 
-/// `powershell Add-AppxPackage -Path [path]` or equivalent - install `path` as an appx package
+/// `powershell Add-AppxPackage -Path (Convert-Path -LiteralPath [path])` or equivalent - install `path` (taken literally, without wildcards) as an appx package
+///
+/// See [PowerShellBackend](crate::powershell::PowerShellBackend) to use another PowerShell executable, and [AppxError::from_io_error](crate::AppxError::from_io_error) to decode failures.
 ///
 /// Might use [winrt](https://docs.rs/winrt/) in the future, possibly behind a feature for WinRT app compatability
 pub fn add_appx_package(path: impl AsRef<Path>) -> io::Result<()> {
-    if !cfg!(windows) { return Err(io::Error::new(io::ErrorKind::Other, "add_appx_package: not implemented on this platform")); }
+    if !cfg!(windows) { return Err(io::Error::new(io::ErrorKind::Unsupported, "add_appx_package: not implemented on this platform")); }
 
     let path = path.as_ref();
     if !path.exists() { return Err(io::Error::new(io::ErrorKind::NotFound, "add_appx_package: `path` does not exist")); }
-    let mut cmd = std::process::Command::new("powershell");
-    cmd.arg("Add-AppxPackage");
-    cmd.arg("-Path").arg(path);
-    let status = cmd.status().map_err(|err| io::Error::new(err.kind(), "add_appx_package: `powershell Add-AppxPackage -Path ...` failed to launch"))?;
-    match status.code() {
-        Some(0) => Ok(()),
-        Some(n) => Err(io::Error::new(io::ErrorKind::Other, format!("add_appx_package: `powershell Add-AppxPackage -Path ...` failed (exit code {})", n))),
-        None    => Err(io::Error::new(io::ErrorKind::Other,         "add_appx_package: `powershell Add-AppxPackage -Path ...` failed (signal)")),
-    }
+    crate::powershell::PowerShellBackend::new().add_appx_package(path)
 }
 
 
 
-#[cfg(not(windows))] mod imp {
-    use super::*;
-    pub(super) fn families() -> io::Result<impl Iterator<Item = PackageFamilyName>> { Ok(None.into_iter()) }
-    pub(super) fn packages() -> io::Result<impl Iterator<Item = PackageFullName  >> { Ok(None.into_iter()) }
-    pub(super) fn packages_for_family(_family: &PackageFamilyName) -> io::Result<std::vec::IntoIter<PackageFullName>> { Err(io::Error::from(io::ErrorKind::NotFound)) }
-}
-
+pub(crate) const FAMILIES : &[u16] = wchar::wch!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Families");
+pub(crate) const PACKAGES : &[u16] = wchar::wch!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages");
 
+/// Test fixture: register `pfn` in `reg` like an install would: a `FAMILIES\{family}\{pfn}` key, and a `PACKAGES\{pfn}` key with the family as its `DisplayName`
+#[cfg(test)] pub(crate) fn test_install(reg: &crate::registry::MemoryRegistry, pfn: &str) {
+    let fam = PackageFullName::new(pfn).unwrap().family_name();
+    reg.create_key(&format!(r"{}\{}\{}", String::from_utf16_lossy(FAMILIES), fam, pfn));
+    reg.set_value(&format!(r"{}\{}", String::from_utf16_lossy(PACKAGES), pfn), "DisplayName", crate::registry::RawValue::sz(&fam.to_string()));
+}
 
-#[cfg(windows)] mod imp {
-    use super::*;
+/// The names of the subkeys of `key`.  Ends after the first error, rather than retrying the same index forever.
+struct SubkeysIter {
+    key:    Option<reg::Key>,
+    index:  u32,
+}
 
-    pub(super) fn families() -> io::Result<impl Iterator<Item = PackageFamilyName>> {
-        Ok(FamiliesIter {
-            key: reg::Key::hkcr(winstr0!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Families"), reg::Options::NONE, reg::SAM::READ_ONLY)?,
-            index: 0,
-        })
-    }
+impl SubkeysIter {
+    fn new(key: reg::Key) -> Self { Self { key: Some(key), index: 0 } }
+}
 
-    struct FamiliesIter {
-        key:    reg::Key,
-        index:  u32,
+impl Iterator for SubkeysIter {
+    type Item = io::Result<WString>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.key.as_ref()?;
+        match key.enum_key(self.index) {
+            Ok(Some(name))  => { self.index += 1; Some(Ok(name)) },
+            Ok(None)        => { self.key = None; None },
+            Err(err)        => { self.key = None; Some(Err(err)) },
+        }
     }
+}
 
-    impl Iterator for FamiliesIter {
-        type Item = PackageFamilyName;
-        fn next(&mut self) -> Option<Self::Item> {
-            let mut pfn = Default::default();
-            let pfn = self.key.enum_key_w(self.index, &mut pfn).ok()??;
-            self.index += 1;
-            Some(PackageFamilyName(WString::new(pfn)))
+struct PackageInfosIter(SubkeysIter);
+
+impl Iterator for PackageInfosIter {
+    type Item = io::Result<PackageInfo>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let pfn = match self.0.next()? {
+                Ok(pfn)     => PackageFullName(pfn),
+                Err(err)    => return Some(Err(err)),
+            };
+            let packages = self.0.key.as_ref()?;
+            match PackageInfo::load_from(packages, &pfn) {
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue, // removed mid-iteration
+                result => return Some(result),
+            }
         }
     }
+}
 
-    pub(super) fn packages() -> io::Result<impl Iterator<Item = PackageFullName>> {
-        Ok(PackagesIter {
-            key: reg::Key::hkcr(winstr0!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages"), reg::Options::NONE, reg::SAM::READ_ONLY)?,
-            index: 0,
-        })
-    }
 
-    pub(super) fn packages_for_family(family: &PackageFamilyName) -> io::Result<impl Iterator<Item = PackageFullName>> {
-        Ok(PackagesIter {
-            key: reg::Key::hkcr(winstr0!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Families"), reg::Options::NONE, reg::SAM::READ_ONLY)?
-                .subkey(family.units0(), reg::Options::NONE, reg::SAM::READ_ONLY)?,
-            index: 0,
-        })
-    }
 
-    struct PackagesIter {
-        key:    reg::Key,
-        index:  u32,
+#[test] fn test_repository_in() {
+    use crate::registry::MemoryRegistry;
+    let reg = MemoryRegistry::new();
+    test_install(&reg, "NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe");
+    test_install(&reg, "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc");
+    test_install(&reg, "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_neutral_split.scale-100_79rhkp1fndgsc");
+
+    let families = families_in(&reg).unwrap().collect::<io::Result<Vec<_>>>().unwrap();
+    assert_eq!(families.len(), 2);
+    assert_eq!(families[0].to_string(), "CanonicalGroupLimited.UbuntuonWindows_79rhkp1fndgsc");
+    assert_eq!(packages_in(&reg).unwrap().count(), 3);
+    assert_eq!(packages_for_family_in(&reg, &families[0]).unwrap().count(), 2);
+    assert_eq!(packages_for_family_in(&reg, &families[1]).unwrap().map(|p| p.unwrap().to_string()).collect::<Vec<_>>(), ["NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe"]);
+
+    assert!( has_family_in(&reg, &PackageFamilyName::new("ncsiuwpapp_8wekyb3d8bbwe").unwrap()));
+    assert!(!has_family_in(&reg, &PackageFamilyName::new("Missing_8wekyb3d8bbwe").unwrap()));
+    assert!( has_package_in(&reg, &PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap()));
+    assert!(!has_package_in(&reg, &PackageFullName::new("NcsiUwpApp_1000.19041.423.1_neutral_neutral_8wekyb3d8bbwe").unwrap()));
+    assert!(packages_for_family_in(&reg, &PackageFamilyName::new("Missing_8wekyb3d8bbwe").unwrap()).is_err());
+    assert!(families_in(&MemoryRegistry::new()).is_err());
+
+    let infos = package_infos_in(&reg).unwrap().map(|info| info.unwrap().full_name().to_string()).collect::<Vec<_>>();
+    assert_eq!(infos, packages_in(&reg).unwrap().map(|p| p.unwrap().to_string()).collect::<Vec<_>>());
+
+    // an unreadable entry is surfaced (once), not silently treated as the end of the list
+    struct Flaky;
+    impl RegistryBackend for Flaky { fn open(&self, _path: &[u16]) -> io::Result<Box<dyn crate::registry::RegistryKey>> { Ok(Box::new(Flaky)) } }
+    impl crate::registry::RegistryKey for Flaky {
+        fn subkey(&self, _path: &[u16]) -> io::Result<Box<dyn crate::registry::RegistryKey>> { Ok(Box::new(Flaky)) }
+        fn enum_key(&self, index: u32) -> io::Result<Option<WString>> { if index == 0 { Ok(Some(WString::from("NcsiUwpApp_8wekyb3d8bbwe"))) } else { Err(io::Error::from(io::ErrorKind::PermissionDenied)) } }
+        fn enum_value(&self, _index: u32) -> io::Result<Option<(WString, crate::registry::RawValue)>> { Ok(None) }
+        fn value(&self, _name: &[u16]) -> io::Result<crate::registry::RawValue> { Err(io::Error::from(io::ErrorKind::NotFound)) }
     }
-
-    impl Iterator for PackagesIter {
-        type Item = PackageFullName;
-        fn next(&mut self) -> Option<Self::Item> {
-            let mut pfn = Default::default();
-            let pfn = self.key.enum_key_w(self.index, &mut pfn).ok()??;
-            self.index += 1;
-            Some(PackageFullName(WString::new(pfn)))
-        }
+    let flaky = families_in(&Flaky).unwrap().map(|f| f.map_err(|err| err.kind())).collect::<Vec<_>>();
+    assert_eq!(flaky.len(), 2);
+    assert!(flaky[0].is_ok());
+    assert_eq!(flaky[1].as_ref().unwrap_err(), &io::ErrorKind::PermissionDenied);
+
+    if !cfg!(windows) {
+        assert_eq!(self::families().err().unwrap().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(packages().err().unwrap().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(packages_for_family(&families[0]).err().unwrap().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(package_infos().err().unwrap().kind(), io::ErrorKind::Unsupported);
     }
 }
@@ -0,0 +1,157 @@
+use std::fmt::{self, Display, Formatter};
+
+
+
+/// e.g. `neutral`, (blank), `~`, `split.scale-100` -
+/// The `ResourceId` field of a [PackageFullName](crate::PackageFullName).
+///
+/// ### Examples
+///
+/// | Value             | Variant                               | Package kind |
+/// | ----------------- | ------------------------------------- | ------------ |
+/// | (blank)           | [None](ResourceId::None)              | main package
+/// | `neutral`         | [Neutral](ResourceId::Neutral)        | main package
+/// | `~`               | [Bundle](ResourceId::Bundle)          | bundle
+/// | `split.scale-100` | [Split](ResourceId::Split)            | resource package
+/// | `foo`             | [Other](ResourceId::Other)            | main package with a developer specified `ResourceId`
+///
+/// ### Corresponds to
+///
+/// `powershell Get-AppxPackage ^| Format-Table -Property ResourceId`<br>
+/// [Identity@ResourceId](https://docs.microsoft.com/en-us/uwp/schemas/appxpackage/appxmanifestschema/element-identity)
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceId {
+    /// (blank)
+    None,
+    /// `neutral`
+    Neutral,
+    /// `~` - an appx bundle
+    Bundle,
+    /// `split.*` - a resource package split out of a bundle
+    Split(Vec<ResourceQualifier>),
+    /// Anything else - e.g. a developer specified `ResourceId`
+    Other(String),
+}
+
+impl ResourceId {
+    /// Parse [code units](https://unicode.org/glossary/#code_unit).  Unrecognized values become [Other](ResourceId::Other).
+    pub fn from_units(units: &[u16]) -> Self { Self::from(String::from_utf16_lossy(units).as_str()) }
+
+    /// Is this a resource package (`split.*`)?
+    pub fn is_resource_package(&self) -> bool { matches!(self, ResourceId::Split(_)) }
+
+    /// Is this a bundle (`~`)?
+    pub fn is_bundle(&self) -> bool { matches!(self, ResourceId::Bundle) }
+
+    /// The qualifiers of a `split.*` resource package, or `&[]`
+    pub fn qualifiers(&self) -> &[ResourceQualifier] {
+        match self {
+            ResourceId::Split(q)    => &q[..],
+            _                       => &[],
+        }
+    }
+}
+
+impl From<&str> for ResourceId {
+    fn from(value: &str) -> Self {
+        match value {
+            ""          => ResourceId::None,
+            "neutral"   => ResourceId::Neutral,
+            "~"         => ResourceId::Bundle,
+            other       => match other.strip_prefix("split.") {
+                Some(q) => ResourceId::Split(q.split('.').map(ResourceQualifier::from).collect()),
+                None    => ResourceId::Other(other.into()),
+            },
+        }
+    }
+}
+
+impl Display for ResourceId {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            ResourceId::None        => Ok(()),
+            ResourceId::Neutral     => fmt.write_str("neutral"),
+            ResourceId::Bundle      => fmt.write_str("~"),
+            ResourceId::Other(o)    => fmt.write_str(o),
+            ResourceId::Split(q)    => {
+                fmt.write_str("split")?;
+                for q in q.iter() { write!(fmt, ".{}", q)?; }
+                Ok(())
+            },
+        }
+    }
+}
+
+
+
+/// e.g. `scale-100`, `language-en`, `dxfeaturelevel-dx11` -
+/// A qualifier of a [ResourceId::Split] resource package.
+///
+/// ### Corresponds to
+///
+/// [Tailor your resources for language, scale, high contrast, and other qualifiers](https://docs.microsoft.com/en-us/windows/uwp/app-resources/tailor-resources-lang-scale-contrast)
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceQualifier {
+    /// `scale-100`, `scale-200`, ...
+    Scale(u16),
+    /// `language-en`, `language-fr-fr`, ...
+    Language(String),
+    /// `dxfeaturelevel-dx9`, `dxfeaturelevel-dx11`, ...
+    DxFeatureLevel(u8),
+    /// Anything else
+    Other(String),
+}
+
+impl From<&str> for ResourceQualifier {
+    fn from(value: &str) -> Self {
+        let parsed = if let Some(scale) = value.strip_prefix("scale-") {
+            scale.parse().ok().map(ResourceQualifier::Scale)
+        } else if let Some(lang) = value.strip_prefix("language-") {
+            Some(ResourceQualifier::Language(lang.into())).filter(|_| !lang.is_empty())
+        } else if let Some(dx) = value.strip_prefix("dxfeaturelevel-dx") {
+            dx.parse().ok().map(ResourceQualifier::DxFeatureLevel)
+        } else {
+            None
+        };
+        // Anything that wouldn't round trip exactly (e.g. `scale-0100`) is kept as-is
+        parsed.filter(|q| q.to_string() == value).unwrap_or_else(|| ResourceQualifier::Other(value.into()))
+    }
+}
+
+impl Display for ResourceQualifier {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            ResourceQualifier::Scale(s)             => write!(fmt, "scale-{}", s),
+            ResourceQualifier::Language(l)          => write!(fmt, "language-{}", l),
+            ResourceQualifier::DxFeatureLevel(dx)   => write!(fmt, "dxfeaturelevel-dx{}", dx),
+            ResourceQualifier::Other(o)             => fmt.write_str(o),
+        }
+    }
+}
+
+
+
+#[test] fn test_resource_id() {
+    use ResourceQualifier::*;
+
+    assert_eq!(ResourceId::from(""),                            ResourceId::None);
+    assert_eq!(ResourceId::from("neutral"),                     ResourceId::Neutral);
+    assert_eq!(ResourceId::from("~"),                           ResourceId::Bundle);
+    assert_eq!(ResourceId::from("split.scale-100"),             ResourceId::Split(vec![Scale(100)]));
+    assert_eq!(ResourceId::from("split.language-en-us"),        ResourceId::Split(vec![Language("en-us".into())]));
+    assert_eq!(ResourceId::from("split.dxfeaturelevel-dx11"),   ResourceId::Split(vec![DxFeatureLevel(11)]));
+    assert_eq!(ResourceId::from("split.scale-0100"),            ResourceId::Split(vec![Other("scale-0100".into())]));
+    assert_eq!(ResourceId::from("split.language-"),             ResourceId::Split(vec![Other("language-".into())]));
+    assert_eq!(ResourceId::from("foo"),                         ResourceId::Other("foo".into()));
+
+    for &s in &["", "neutral", "~", "split.scale-100", "split.language-en-us", "split.dxfeaturelevel-dx11", "split.scale-0100", "split.", "split.a.b", "foo"] {
+        assert_eq!(ResourceId::from(s).to_string(), s);
+    }
+
+    assert!( ResourceId::from("split.scale-100").is_resource_package());
+    assert!(!ResourceId::from("neutral").is_resource_package());
+    assert!(!ResourceId::from("~").is_resource_package());
+    assert!( ResourceId::from("~").is_bundle());
+    assert_eq!(ResourceId::from("split.scale-150").qualifiers(), &[Scale(150)]);
+    assert_eq!(ResourceId::None.qualifiers(), &[]);
+}
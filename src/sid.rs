@@ -0,0 +1,163 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+
+
+/// e.g. `S-1-15-2-3624051433-2125758914-1423191267-1740899205-1073925389-3782572162-737981194` -
+/// A [security identifier](https://docs.microsoft.com/en-us/windows/win32/secauthz/security-identifiers).
+///
+/// Convertible to/from both the string form (`S-1-...`) and the binary form (a [SID] structure's bytes).
+///
+/// ### Examples
+///
+/// ```rust
+/// let sid : appx::Sid = "S-1-15-3-1".parse().unwrap();
+/// assert_eq!(sid.to_string(), "S-1-15-3-1");
+/// assert_eq!(sid.to_bytes(), [1, 2, 0, 0, 0, 0, 0, 15, 3, 0, 0, 0, 1, 0, 0, 0]);
+/// ```
+///
+/// [SID]:  https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-sid
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sid {
+    authority:          u64,
+    sub_authorities:    Vec<u32>,
+}
+
+impl Sid {
+    /// `SID_REVISION`
+    pub const REVISION : u8 = 1;
+
+    /// `SID_MAX_SUB_AUTHORITIES`
+    pub const MAX_SUB_AUTHORITIES : usize = 15;
+
+    /// `SECURITY_APP_PACKAGE_AUTHORITY` (`S-1-15-...`)
+    pub const APP_PACKAGE_AUTHORITY : u64 = 15;
+
+    /// Construct a [Sid] from an identifier authority (48 bits) and up to 15 sub-authorities
+    pub fn new(authority: u64, sub_authorities: impl Into<Vec<u32>>) -> Result<Self, SidError> {
+        let sub_authorities = sub_authorities.into();
+        if authority >= (1 << 48) { return Err(SidError(())); }
+        if sub_authorities.len() > Self::MAX_SUB_AUTHORITIES { return Err(SidError(())); }
+        Ok(Self { authority, sub_authorities })
+    }
+
+    /// The 48-bit identifier authority (e.g. `15` for `S-1-15-...`)
+    pub fn authority(&self) -> u64 { self.authority }
+
+    /// The sub-authorities (e.g. `[3, 1]` for `S-1-15-3-1`)
+    pub fn sub_authorities(&self) -> &[u32] { &self.sub_authorities[..] }
+
+    /// Length of the binary form, in bytes
+    pub fn byte_len(&self) -> usize { 8 + 4 * self.sub_authorities.len() }
+
+    /// The binary form (the bytes of a [SID](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-sid) structure)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.byte_len());
+        bytes.push(Self::REVISION);
+        bytes.push(self.sub_authorities.len() as u8);
+        bytes.extend_from_slice(&self.authority.to_be_bytes()[2..]);
+        for sa in self.sub_authorities.iter() { bytes.extend_from_slice(&sa.to_le_bytes()); }
+        bytes
+    }
+
+    /// Parse the binary form.  `bytes` must be exactly [byte_len](Self::byte_len) bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SidError> {
+        let (sid, len) = Self::from_bytes_prefix(bytes)?;
+        if len != bytes.len() { return Err(SidError(())); }
+        Ok(sid)
+    }
+
+    /// Parse the binary form from the start of `bytes`, returning the [Sid] and how many bytes it took up
+    pub(crate) fn from_bytes_prefix(bytes: &[u8]) -> Result<(Self, usize), SidError> {
+        if bytes.len() < 8 || bytes[0] != Self::REVISION { return Err(SidError(())); }
+        let count = usize::from(bytes[1]);
+        let len = 8 + 4 * count;
+        if count > Self::MAX_SUB_AUTHORITIES || bytes.len() < len { return Err(SidError(())); }
+
+        let mut authority = [0u8; 8];
+        authority[2..].copy_from_slice(&bytes[2..8]);
+        let sub_authorities = bytes[8..len].chunks_exact(4).map(|sa| u32::from_le_bytes([sa[0], sa[1], sa[2], sa[3]])).collect();
+        Ok((Self { authority: u64::from_be_bytes(authority), sub_authorities }, len))
+    }
+}
+
+impl Debug for Sid { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { write!(fmt, "Sid({})", self) } }
+
+impl Display for Sid {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        // Authorities >= 2^32 are written in hex, per ConvertSidToStringSidW
+        if self.authority >> 32 == 0 {
+            write!(fmt, "S-{}-{}", Self::REVISION, self.authority)?;
+        } else {
+            write!(fmt, "S-{}-0x{:012X}", Self::REVISION, self.authority)?;
+        }
+        for sa in self.sub_authorities.iter() { write!(fmt, "-{}", sa)?; }
+        Ok(())
+    }
+}
+
+impl FromStr for Sid {
+    type Err = SidError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('-');
+        if !matches!(parts.next(), Some("S") | Some("s")) { return Err(SidError(())); }
+        if parts.next() != Some("1") { return Err(SidError(())); }
+        let authority = parts.next().ok_or(SidError(()))?;
+        let authority = match authority.strip_prefix("0x").or_else(|| authority.strip_prefix("0X")) {
+            Some(hex)   => u64::from_str_radix(hex, 16),
+            None        => authority.parse(),
+        }.map_err(|_| SidError(()))?;
+        let sub_authorities = parts.map(|sa| sa.parse::<u32>().map_err(|_| SidError(()))).collect::<Result<Vec<_>, _>>()?;
+        Self::new(authority, sub_authorities)
+    }
+}
+
+impl TryFrom<&[u8]> for Sid { fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> { Self::from_bytes(bytes) } type Error = SidError; }
+
+
+
+/// A [Sid] was malformed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SidError(());
+
+impl Error   for SidError {}
+impl Display for SidError { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { fmt.write_str("malformed security identifier") } }
+
+
+
+/// Build a `S-1-15-{prefix...}-{hash...}` [Sid] from the SHA-256 of `name` (UTF-16LE), using the first `hash_sub_authorities` 32-bit words of the hash
+pub(crate) fn app_package_sid_from_hash(prefix: &[u32], name: impl Iterator<Item = u16>, hash_sub_authorities: usize) -> Sid {
+    use sha2::{Digest, Sha256};
+    let mut sha = Sha256::new();
+    for cu in name { sha.update(cu.to_le_bytes()); }
+    let hash = sha.finalize();
+
+    let mut sub_authorities = prefix.to_vec();
+    sub_authorities.extend(hash.chunks_exact(4).take(hash_sub_authorities).map(|sa| u32::from_le_bytes([sa[0], sa[1], sa[2], sa[3]])));
+    Sid { authority: Sid::APP_PACKAGE_AUTHORITY, sub_authorities }
+}
+
+
+
+#[test] fn test_sid() {
+    for &s in &["S-1-15-3-1", "S-1-5-18", "S-1-0-0", "S-1-15-2-3624051433-2125758914-1423191267-1740899205-1073925389-3782572162-737981194", "S-1-0x123456789ABC-1"] {
+        let sid : Sid = s.parse().unwrap();
+        assert_eq!(sid.to_string(), s);
+        assert_eq!(Sid::from_bytes(&sid.to_bytes()), Ok(sid.clone()));
+        assert_eq!(sid.to_bytes().len(), sid.byte_len());
+    }
+
+    assert_eq!("S-1-5-18".parse::<Sid>().unwrap().to_bytes(), [1, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0]);
+    assert!("S-2-5-18"  .parse::<Sid>().is_err());
+    assert!("X-1-5-18"  .parse::<Sid>().is_err());
+    assert!("S-1"       .parse::<Sid>().is_err());
+    assert!("S-1-5-x"   .parse::<Sid>().is_err());
+    assert!("S-1-281474976710656".parse::<Sid>().is_err()); // authority >= 2^48
+    assert!("S-1-5-1-2-3-4-5-6-7-8-9-10-11-12-13-14-15-16".parse::<Sid>().is_err());
+
+    assert!(Sid::from_bytes(&[1, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0]).is_err());         // truncated
+    assert!(Sid::from_bytes(&[1, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0, 0]).is_err());   // trailing data
+    assert!(Sid::from_bytes(&[2, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0]).is_err());      // bad revision
+}
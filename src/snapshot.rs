@@ -0,0 +1,317 @@
+use crate::{ordinal, reg, repository};
+use crate::{PackageFamilyName, PackageFullName, PackageInfo};
+use crate::registry::{RegistryBackend, Win32Registry};
+
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+
+
+/// The installed packages of a repository at a point in time, grouped by family.
+///
+/// Capture one before and after a deployment, then [diff](Self::diff) them.  With the `serde` feature, round trips through JSON:
+///
+/// ```json
+/// { "families": { "NcsiUwpApp_8wekyb3d8bbwe": [{ "full_name": "NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe", "display_name": "NcsiUwpApp", "install_location": "C:\\WINDOWS\\SystemApps\\NcsiUwpApp_8wekyb3d8bbwe" }] } }
+/// ```
+///
+/// ### Examples
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "serde")] fn main() {
+/// let before = appx::RepositorySnapshot::capture().unwrap();
+/// std::fs::write("before.json", before.to_json().unwrap()).unwrap();
+/// // ...deploy...
+/// let before = appx::RepositorySnapshot::from_json(&std::fs::read_to_string("before.json").unwrap()).unwrap();
+/// let after  = appx::RepositorySnapshot::capture().unwrap();
+/// print!("{}", before.diff(&after));
+/// # }
+/// # #[cfg(not(feature = "serde"))] fn main() {}
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RepositorySnapshot {
+    families: BTreeMap<PackageFamilyName, Vec<PackageSnapshot>>,
+}
+
+/// A package of a [RepositorySnapshot], with a few key properties
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PackageSnapshot {
+    full_name:          PackageFullName,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))] display_name:        Option<String>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))] install_location:    Option<PathBuf>,
+}
+
+/// The differences between two [RepositorySnapshot]s, per family (see [RepositorySnapshot::diff])
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepositoryDiff {
+    families: Vec<FamilyDiff>,
+}
+
+/// The differences between two [RepositorySnapshot]s within a single family
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FamilyDiff {
+    family:             PackageFamilyName,
+    added:              Vec<PackageFullName>,
+    removed:            Vec<PackageFullName>,
+    version_changed:    Vec<(PackageFullName, PackageFullName)>,
+}
+
+impl RepositorySnapshot {
+    /// Capture the packages installed on this computer
+    pub fn capture() -> io::Result<Self> { Self::capture_in(&Win32Registry) }
+
+    /// Capture the packages listed in `registry`
+    pub fn capture_in(registry: &dyn RegistryBackend) -> io::Result<Self> {
+        let packages = match reg::Key::open(registry, repository::PACKAGES) {
+            Ok(packages)                                        => Some(packages),
+            Err(err) if err.kind() == io::ErrorKind::NotFound   => None,
+            Err(err)                                            => return Err(err),
+        };
+        let mut snapshot = Self::default();
+        for family in repository::families_in(registry)? {
+            let family = family?;
+            let pfns = match repository::packages_for_family_in(registry, &family) {
+                Ok(pfns)                                            => pfns.collect::<io::Result<Vec<_>>>()?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound   => continue, // removed mid-capture
+                Err(err)                                            => return Err(err),
+            };
+            let mut snapshots = Vec::with_capacity(pfns.len());
+            for pfn in pfns {
+                snapshots.push(match packages.as_ref().map(|packages| PackageInfo::load_from(packages, &pfn)) {
+                    Some(Ok(info))                                              => PackageSnapshot::from(&info),
+                    Some(Err(err)) if err.kind() != io::ErrorKind::NotFound     => return Err(err),
+                    _                                                           => PackageSnapshot::new(pfn), // no Packages\... entry: just the name
+                });
+            }
+            snapshot.insert_family(family, snapshots);
+        }
+        Ok(snapshot)
+    }
+
+    /// Build a snapshot from packages (e.g. from another source like `Get-AppxPackage`), grouped by [PackageFullName::family_name]
+    pub fn from_packages(packages: impl IntoIterator<Item = PackageSnapshot>) -> Self {
+        let mut families = BTreeMap::<PackageFamilyName, Vec<PackageSnapshot>>::new();
+        for pkg in packages { families.entry(pkg.full_name.family_name()).or_default().push(pkg); }
+        let mut snapshot = Self::default();
+        for (family, packages) in families { snapshot.insert_family(family, packages); }
+        snapshot
+    }
+
+    /// Parse a snapshot previously written by [to_json](Self::to_json) (requires the `serde` feature).
+    ///
+    /// Like names enumerated from the registry, the [PackageFamilyName]s and [PackageFullName]s aren't validated: check them with [PackageFamilyName::validate] / [PackageFullName::validate] if needed.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        let mut snapshot = serde_json::from_str::<Self>(json).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        for packages in snapshot.families.values_mut() { packages.sort_by(|a, b| a.full_name.cmp(&b.full_name)); }
+        Ok(snapshot)
+    }
+
+    /// Write this snapshot as (pretty printed) JSON (requires the `serde` feature).
+    ///
+    /// Fails with [InvalidData](io::ErrorKind::InvalidData) if the snapshot can't be represented as JSON (e.g. a non-UTF-8 install location.)
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> io::Result<String> { serde_json::to_string_pretty(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)) }
+
+    /// The families in this snapshot, in order
+    pub fn families(&self) -> impl Iterator<Item = &PackageFamilyName> { self.families.keys() }
+
+    /// The packages of `family` in this snapshot, in order (empty if the family isn't present)
+    pub fn packages_for_family(&self, family: &PackageFamilyName) -> &[PackageSnapshot] { self.families.get(family).map_or(&[], |p| &p[..]) }
+
+    /// Every package in this snapshot, in order
+    pub fn packages(&self) -> impl Iterator<Item = &PackageSnapshot> { self.families.values().flatten() }
+
+    /// Compare against a `newer` snapshot
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use appx::*;
+    /// let pkg = |pfn: &str| PackageSnapshot::new(PackageFullName::new(pfn).unwrap());
+    /// let before = RepositorySnapshot::from_packages(vec![pkg("Foo_1.0.0.0_x64__8wekyb3d8bbwe"), pkg("Bar_1.0.0.0_x64__8wekyb3d8bbwe")]);
+    /// let after  = RepositorySnapshot::from_packages(vec![pkg("Foo_2.0.0.0_x64__8wekyb3d8bbwe"), pkg("Baz_1.0.0.0_x64__8wekyb3d8bbwe")]);
+    /// assert_eq!(before.diff(&after).to_string(), "\
+    ///     - Bar_1.0.0.0_x64__8wekyb3d8bbwe\n\
+    ///     + Baz_1.0.0.0_x64__8wekyb3d8bbwe\n\
+    ///     ~ Foo_1.0.0.0_x64__8wekyb3d8bbwe -> Foo_2.0.0.0_x64__8wekyb3d8bbwe\n\
+    /// ");
+    /// ```
+    pub fn diff(&self, newer: &RepositorySnapshot) -> RepositoryDiff {
+        let empty = Vec::new();
+        let mut families = self.families.keys().chain(newer.families.keys()).collect::<Vec<_>>();
+        families.sort();
+        families.dedup();
+
+        RepositoryDiff { families: families.into_iter().filter_map(|family| {
+            let old = self .families.get(family).unwrap_or(&empty).iter().map(|p| &p.full_name).collect::<Vec<_>>();
+            let new = newer.families.get(family).unwrap_or(&empty).iter().map(|p| &p.full_name).collect::<Vec<_>>();
+            let mut removed = old.iter().filter(|pfn| !new.contains(pfn)).map(|&pfn| pfn.clone()).collect::<Vec<_>>();
+            let mut added   = new.iter().filter(|pfn| !old.contains(pfn)).map(|&pfn| pfn.clone()).collect::<Vec<_>>();
+
+            // pair up removed/added packages that differ only by version (same architecture and resource id)
+            let mut version_changed = Vec::new();
+            removed.sort();
+            added.sort();
+            let mut i = 0;
+            while i < removed.len() {
+                match added.iter().position(|a| same_but_version(a, &removed[i])) {
+                    Some(a) => version_changed.push((removed.remove(i), added.remove(a))),
+                    None    => i += 1,
+                }
+            }
+
+            if added.is_empty() && removed.is_empty() && version_changed.is_empty() { return None; }
+            Some(FamilyDiff { family: family.clone(), added, removed, version_changed })
+        }).collect() }
+    }
+
+    fn insert_family(&mut self, family: PackageFamilyName, mut packages: Vec<PackageSnapshot>) {
+        packages.sort_by(|a, b| a.full_name.cmp(&b.full_name));
+        self.families.insert(family, packages);
+    }
+}
+
+fn same_but_version(a: &PackageFullName, b: &PackageFullName) -> bool {
+    ordinal::eq_ignore_case(a.name(),               b.name()) &&
+    ordinal::eq_ignore_case(a.architecture_units(), b.architecture_units()) &&
+    ordinal::eq_ignore_case(a.resource_id_units(),  b.resource_id_units()) &&
+    ordinal::eq_ignore_case(a.publisher_id(),       b.publisher_id())
+}
+
+impl PackageSnapshot {
+    /// A package snapshot without any properties
+    pub fn new(full_name: PackageFullName) -> Self { Self { full_name, display_name: None, install_location: None } }
+
+    /// Set `DisplayName`
+    pub fn with_display_name(self, display_name: impl Into<String>) -> Self { Self { display_name: Some(display_name.into()), ..self } }
+
+    /// Set `InstallLocation`
+    pub fn with_install_location(self, install_location: impl Into<PathBuf>) -> Self { Self { install_location: Some(install_location.into()), ..self } }
+
+    /// The package
+    pub fn full_name(&self) -> &PackageFullName { &self.full_name }
+
+    /// `DisplayName`, if known
+    pub fn display_name(&self) -> Option<&str> { self.display_name.as_deref() }
+
+    /// `InstallLocation` (powershell) / `PackageRootFolder` (registry), if known
+    pub fn install_location(&self) -> Option<&Path> { self.install_location.as_deref() }
+}
+
+impl From<&PackageInfo> for PackageSnapshot {
+    fn from(info: &PackageInfo) -> Self {
+        Self {
+            full_name:          info.full_name().clone(),
+            display_name:       info.display_name().map(String::from),
+            install_location:   info.install_location().map(PathBuf::from),
+        }
+    }
+}
+
+impl RepositoryDiff {
+    /// `true` if the snapshots had the same packages
+    pub fn is_empty(&self) -> bool { self.families.is_empty() }
+
+    /// The families with changes, in order
+    pub fn families(&self) -> &[FamilyDiff] { &self.families }
+}
+
+/// One line per change: `+ added`, `- removed`, `~ old -> new`
+impl Display for RepositoryDiff {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        for family in self.families.iter() { Display::fmt(family, fmt)?; }
+        Ok(())
+    }
+}
+
+impl FamilyDiff {
+    /// The family these changes are in
+    pub fn family(&self) -> &PackageFamilyName { &self.family }
+
+    /// Packages only in the newer snapshot (excluding [version_changed](Self::version_changed))
+    pub fn added(&self) -> &[PackageFullName] { &self.added }
+
+    /// Packages only in the older snapshot (excluding [version_changed](Self::version_changed))
+    pub fn removed(&self) -> &[PackageFullName] { &self.removed }
+
+    /// `(old, new)` packages that differ only by `Version` (e.g. upgrades and downgrades)
+    pub fn version_changed(&self) -> &[(PackageFullName, PackageFullName)] { &self.version_changed }
+}
+
+impl Display for FamilyDiff {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        for pfn in self.removed.iter()              { writeln!(fmt, "- {}", pfn)?; }
+        for pfn in self.added.iter()                { writeln!(fmt, "+ {}", pfn)?; }
+        for (old, new) in self.version_changed.iter() { writeln!(fmt, "~ {} -> {}", old, new)?; }
+        Ok(())
+    }
+}
+
+
+
+#[test] fn test_repository_snapshot() {
+    use crate::registry::{MemoryRegistry, RawValue, RegistryKey};
+    use crate::repository::{test_install as install, FAMILIES};
+    use crate::WString;
+
+    let before = MemoryRegistry::new();
+    install(&before, "NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe");
+    install(&before, "CanonicalGroupLimited.UbuntuonWindows_1804.2020.812.0_x64__79rhkp1fndgsc");
+    install(&before, "CanonicalGroupLimited.UbuntuonWindows_1804.2020.812.0_neutral_split.scale-100_79rhkp1fndgsc");
+    let after = MemoryRegistry::new();
+    install(&after, "NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe");
+    install(&after, "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc");
+    install(&after, "Microsoft.WindowsStore_12010.1001.3.0_x64__8wekyb3d8bbwe");
+    after.create_key(&format!(r"{}\Empty_8wekyb3d8bbwe", String::from_utf16_lossy(FAMILIES)));
+
+    // unreadable package properties fail the capture, rather than being silently dropped
+    struct DenyValues(MemoryRegistry);
+    struct DenyValuesKey(Box<dyn RegistryKey>);
+    impl RegistryBackend for DenyValues { fn open(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>> { Ok(Box::new(DenyValuesKey(self.0.open(path)?))) } }
+    impl RegistryKey for DenyValuesKey {
+        fn subkey(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>> { Ok(Box::new(DenyValuesKey(self.0.subkey(path)?))) }
+        fn enum_key(&self, index: u32) -> io::Result<Option<WString>> { self.0.enum_key(index) }
+        fn enum_value(&self, _index: u32) -> io::Result<Option<(WString, RawValue)>> { Err(io::Error::from(io::ErrorKind::PermissionDenied)) }
+        fn value(&self, _name: &[u16]) -> io::Result<RawValue> { Err(io::Error::from(io::ErrorKind::PermissionDenied)) }
+    }
+    assert_eq!(RepositorySnapshot::capture_in(&DenyValues(before.clone())).unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+
+    let before = RepositorySnapshot::capture_in(&before).unwrap();
+    let after  = RepositorySnapshot::capture_in(&after).unwrap();
+    assert_eq!(before.families().count(), 2);
+    assert_eq!(before.packages().count(), 3);
+    assert_eq!(after.families().count(), 4);
+    assert_eq!(after.packages().next().unwrap().display_name(), Some("CanonicalGroupLimited.UbuntuonWindows_79rhkp1fndgsc"));
+
+    #[cfg(feature = "serde")] {
+        for snapshot in [&before, &after].iter() {
+            let json = snapshot.to_json().unwrap();
+            assert_eq!(&&RepositorySnapshot::from_json(&json).unwrap(), snapshot, "{}", json);
+        }
+        assert_eq!(RepositorySnapshot::from_json("{}").unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        #[cfg(unix)] {
+            use std::os::unix::ffi::OsStringExt;
+            let pkg = PackageSnapshot::new(PackageFullName::new_unchecked("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe"));
+            let pkg = pkg.with_install_location(std::ffi::OsString::from_vec(vec![0xFF]));
+            assert_eq!(RepositorySnapshot::from_packages(vec![pkg]).to_json().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    let diff = before.diff(&after);
+    assert_eq!(diff.families().len(), 2);
+    assert_eq!(diff.to_string(), "\
+        - CanonicalGroupLimited.UbuntuonWindows_1804.2020.812.0_neutral_split.scale-100_79rhkp1fndgsc\n\
+        ~ CanonicalGroupLimited.UbuntuonWindows_1804.2020.812.0_x64__79rhkp1fndgsc -> CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc\n\
+        + Microsoft.WindowsStore_12010.1001.3.0_x64__8wekyb3d8bbwe\n\
+    ");
+    assert!(before.diff(&before).is_empty());
+    assert_eq!(after.diff(&before).families()[0].version_changed()[0].0.to_string(), "CanonicalGroupLimited.UbuntuonWindows_2004.2020.812.0_x64__79rhkp1fndgsc");
+}
@@ -0,0 +1,170 @@
+use crate::{PackageVersion, PackageVersionError, ProcessorArchitecture, ProcessorArchitectureError, PublisherId, PublisherIdError};
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+
+
+/// A [PackageFamilyName](crate::PackageFamilyName) or [PackageFullName](crate::PackageFullName) failed validation
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PackageIdError {
+    /// Package `Name` must be 3-50 characters long
+    NameLength(usize),
+    /// Package `Name` may only contain `A-Z`, `a-z`, `0-9`, `.` and `-`, and may not end with `.`
+    NameCharacter,
+    /// Package `Name` is a reserved file name (e.g. `CON`, `NUL`, `COM1`, `LPT1.foo`)
+    NameReserved,
+    /// Wrong number of `_` separated fields (2 for family names, 5 for full names)
+    FieldCount { expected: usize, found: usize },
+    /// Package `Version` is invalid
+    Version(PackageVersionError),
+    /// Package `Architecture` is invalid
+    Architecture(ProcessorArchitectureError),
+    /// Package `ResourceId` must be blank, `~`, or up to 30 of `A-Z`, `a-z`, `0-9`, `.` and `-`
+    ResourceId,
+    /// Package `PublisherId` is invalid
+    PublisherId(PublisherIdError),
+    /// A required field wasn't specified (e.g. when building a [PackageId](crate::PackageId))
+    MissingField(&'static str),
+    /// An [ApplicationUserModelId](crate::ApplicationUserModelId) must contain exactly one `!`
+    AppIdSeparator,
+    /// An application ID must be 1-64 characters of `.` separated `A-Z`/`a-z` led `A-Z`, `a-z`, `0-9` segments, and not a reserved name
+    AppId,
+}
+
+impl Error for PackageIdError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PackageIdError::Version(err)        => Some(err),
+            PackageIdError::Architecture(err)   => Some(err),
+            PackageIdError::PublisherId(err)    => Some(err),
+            _                                   => None,
+        }
+    }
+}
+
+impl Display for PackageIdError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            PackageIdError::NameLength(n)                   => write!(fmt, "package name must be 3-50 characters long (was {} characters)", n),
+            PackageIdError::NameCharacter                   => write!(fmt, "package name may only contain A-Z, a-z, 0-9, '.' and '-', and may not end with '.'"),
+            PackageIdError::NameReserved                    => write!(fmt, "package name is a reserved name"),
+            PackageIdError::FieldCount { expected, found }  => write!(fmt, "expected {} '_' separated fields, found {}", expected, found),
+            PackageIdError::Version(err)                    => Display::fmt(err, fmt),
+            PackageIdError::Architecture(err)               => Display::fmt(err, fmt),
+            PackageIdError::ResourceId                      => write!(fmt, "resource ID must be blank, '~', or up to 30 of A-Z, a-z, 0-9, '.' and '-'"),
+            PackageIdError::PublisherId(err)                => Display::fmt(err, fmt),
+            PackageIdError::MissingField(field)             => write!(fmt, "missing required field `{}`", field),
+            PackageIdError::AppIdSeparator                  => write!(fmt, "application user model ID must contain exactly one '!'"),
+            PackageIdError::AppId                           => write!(fmt, "application ID must be 1-64 characters of '.' separated segments starting with A-Z or a-z and containing only A-Z, a-z, 0-9, and not a reserved name"),
+        }
+    }
+}
+
+impl From<PackageVersionError>          for PackageIdError { fn from(err: PackageVersionError       ) -> Self { PackageIdError::Version(err) } }
+impl From<ProcessorArchitectureError>   for PackageIdError { fn from(err: ProcessorArchitectureError) -> Self { PackageIdError::Architecture(err) } }
+impl From<PublisherIdError>             for PackageIdError { fn from(err: PublisherIdError          ) -> Self { PackageIdError::PublisherId(err) } }
+
+
+
+const RESERVED_NAMES : &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn is_ascii_identifier(units: &[u16]) -> bool {
+    units.iter().all(|&cu| cu < 0x80 && { let ch = cu as u8; ch.is_ascii_alphanumeric() || ch == b'.' || ch == b'-' })
+}
+
+/// Validate a package `Name` per [Identity@Name](https://docs.microsoft.com/en-us/uwp/schemas/appxpackage/appxmanifestschema/element-identity)
+pub(crate) fn name(units: &[u16]) -> Result<(), PackageIdError> {
+    if !(3 ..= 50).contains(&units.len()) { return Err(PackageIdError::NameLength(units.len())); }
+    if !is_ascii_identifier(units) || units.last() == Some(&u16::from(b'.')) { return Err(PackageIdError::NameCharacter); }
+
+    if is_reserved(units) { return Err(PackageIdError::NameReserved); }
+    Ok(())
+}
+
+/// `CON`, `con`, `CON.foo`, ... are all reserved
+fn is_reserved(units: &[u16]) -> bool {
+    let stem = units.split(|&cu| cu == u16::from(b'.')).next().unwrap_or(units);
+    RESERVED_NAMES.iter().any(|r| r.len() == stem.len() && r.bytes().zip(stem.iter()).all(|(r, &cu)| u16::from(r) == crate::ordinal::upcase(cu)))
+}
+
+/// Validate a package relative application ID per [Application@Id](https://docs.microsoft.com/en-us/uwp/schemas/appxpackage/uapmanifestschema/element-application)
+pub(crate) fn app_id(units: &[u16]) -> Result<(), PackageIdError> {
+    if !(1 ..= 64).contains(&units.len()) || is_reserved(units) { return Err(PackageIdError::AppId); }
+    let valid_segment = |segment: &[u16]| match segment.split_first() {
+        Some((&first, rest))    => first < 0x80 && (first as u8).is_ascii_alphabetic() && rest.iter().all(|&cu| cu < 0x80 && (cu as u8).is_ascii_alphanumeric()),
+        None                    => false,
+    };
+    if !units.split(|&cu| cu == u16::from(b'.')).all(valid_segment) { return Err(PackageIdError::AppId); }
+    Ok(())
+}
+
+pub(crate) fn version(units: &[u16]) -> Result<(), PackageIdError> {
+    PackageVersion::from_units(units)?;
+    Ok(())
+}
+
+/// Validate a package `Architecture` - must be a known architecture (ignoring case, like the rest of a [PackageFullName](crate::PackageFullName))
+pub(crate) fn architecture(units: &[u16]) -> Result<(), PackageIdError> {
+    match ProcessorArchitecture::from_units(units) {
+        Some(ProcessorArchitecture::Unknown) | None => Err(PackageIdError::Architecture(ProcessorArchitectureError(()))),
+        Some(_)                                     => Ok(()),
+    }
+}
+
+pub(crate) fn resource_id(units: &[u16]) -> Result<(), PackageIdError> {
+    if units.is_empty() || units == [u16::from(b'~')] { return Ok(()); }
+    if units.len() > 30 || !is_ascii_identifier(units) { return Err(PackageIdError::ResourceId); }
+    Ok(())
+}
+
+pub(crate) fn publisher_id(units: &[u16]) -> Result<(), PackageIdError> {
+    PublisherId::from_units(units)?;
+    Ok(())
+}
+
+/// Split `units` into exactly `expected` `_` separated fields
+pub(crate) fn fields(units: &[u16], expected: usize) -> Result<Vec<&[u16]>, PackageIdError> {
+    let fields = units.split(|&cu| cu == u16::from(b'_')).collect::<Vec<_>>();
+    if fields.len() != expected { return Err(PackageIdError::FieldCount { expected, found: fields.len() }); }
+    Ok(fields)
+}
+
+
+
+#[test] fn test_validate_name() {
+    assert_eq!(name(wchar::wch!("NcsiUwpApp")), Ok(()));
+    assert_eq!(name(wchar::wch!("CanonicalGroupLimited.Ubuntu20.04onWindows")), Ok(()));
+    assert_eq!(name(wchar::wch!("a-b")), Ok(()));
+    assert_eq!(name(wchar::wch!("CONSOLE")), Ok(()));
+    assert_eq!(name(wchar::wch!("ab")), Err(PackageIdError::NameLength(2)));
+    assert_eq!(name(&[u16::from(b'a'); 51]), Err(PackageIdError::NameLength(51)));
+    assert_eq!(name(wchar::wch!("a_b")), Err(PackageIdError::NameCharacter));
+    assert_eq!(name(wchar::wch!("a b")), Err(PackageIdError::NameCharacter));
+    assert_eq!(name(wchar::wch!("abc.")), Err(PackageIdError::NameCharacter));
+    assert_eq!(name(wchar::wch!("caf\u{E9}")), Err(PackageIdError::NameCharacter));
+    assert_eq!(name(wchar::wch!("CON")), Err(PackageIdError::NameReserved));
+    assert_eq!(name(wchar::wch!("nul")), Err(PackageIdError::NameReserved));
+    assert_eq!(name(wchar::wch!("Com1.Foo")), Err(PackageIdError::NameReserved));
+
+    assert_eq!(app_id(wchar::wch!("App")), Ok(()));
+    assert_eq!(app_id(wchar::wch!("Microsoft.WindowsCalculator2")), Ok(()));
+    assert_eq!(app_id(wchar::wch!("")), Err(PackageIdError::AppId));
+    assert_eq!(app_id(&[u16::from(b'a'); 65]), Err(PackageIdError::AppId));
+    assert_eq!(app_id(wchar::wch!("1App")), Err(PackageIdError::AppId));
+    assert_eq!(app_id(wchar::wch!("App.")), Err(PackageIdError::AppId));
+    assert_eq!(app_id(wchar::wch!("App.2")), Err(PackageIdError::AppId));
+    assert_eq!(app_id(wchar::wch!("My-App")), Err(PackageIdError::AppId));
+    assert_eq!(app_id(wchar::wch!("Aux")), Err(PackageIdError::AppId));
+
+    assert_eq!(resource_id(wchar::wch!("")), Ok(()));
+    assert_eq!(resource_id(wchar::wch!("~")), Ok(()));
+    assert_eq!(resource_id(wchar::wch!("split.scale-100")), Ok(()));
+    assert_eq!(resource_id(wchar::wch!("~~")), Err(PackageIdError::ResourceId));
+    assert_eq!(resource_id(&[u16::from(b'a'); 31]), Err(PackageIdError::ResourceId));
+}
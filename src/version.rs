@@ -0,0 +1,151 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")] use serde::de;
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+
+
+/// e.g. `1000.19041.423.0` -
+/// The `Major.Minor.Build.Revision` version of an appx package.
+///
+/// Unlike the raw code units of a [PackageFullName](crate::PackageFullName), these order numerically:
+/// `10.0.0.0` > `9.0.0.0`.
+///
+/// ### Examples
+///
+/// * `1000.19041.423.0`
+/// * `2004.2020.812.0`
+///
+/// ### Corresponds to
+///
+/// `powershell Get-AppxPackage ^| Format-Table -Property Version`<br>
+/// [PACKAGE_VERSION](https://docs.microsoft.com/en-us/windows/win32/api/appmodel/ns-appmodel-package_version)<br>
+/// [Identity@Version](https://docs.microsoft.com/en-us/uwp/schemas/appxpackage/appxmanifestschema/element-identity)
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PackageVersion {
+    pub major:      u16,
+    pub minor:      u16,
+    pub build:      u16,
+    pub revision:   u16,
+}
+
+impl PackageVersion {
+    pub const fn new(major: u16, minor: u16, build: u16, revision: u16) -> Self { Self { major, minor, build, revision } }
+
+    /// Unpack a `PACKAGE_VERSION::Version` style `u64` (e.g. the `OSMinVersion` registry value)
+    pub const fn from_u64(v: u64) -> Self { Self::new((v >> 48) as u16, (v >> 32) as u16, (v >> 16) as u16, v as u16) }
+
+    /// Pack into a `PACKAGE_VERSION::Version` style `u64`
+    pub const fn to_u64(self) -> u64 { (self.major as u64) << 48 | (self.minor as u64) << 32 | (self.build as u64) << 16 | (self.revision as u64) }
+
+    /// Strictly parse [code units](https://unicode.org/glossary/#code_unit): exactly four `.` separated decimal parts, each `0..=65535`, without leading zeros.
+    pub fn from_units(units: &[u16]) -> Result<Self, PackageVersionError> {
+        let mut parts = [0u16; 4];
+        let mut n = 0;
+        for part in units.split(|&cu| cu == u16::from(b'.')) {
+            let dst = parts.get_mut(n).ok_or(PackageVersionError::TooManyParts)?;
+            *dst = parse_part(part)?;
+            n += 1;
+        }
+        if n < 4 { return Err(PackageVersionError::TooFewParts); }
+        let [major, minor, build, revision] = parts;
+        Ok(Self { major, minor, build, revision })
+    }
+}
+
+fn parse_part(part: &[u16]) -> Result<u16, PackageVersionError> {
+    if part.is_empty() { return Err(PackageVersionError::EmptyPart); }
+    if part.len() > 1 && part[0] == u16::from(b'0') { return Err(PackageVersionError::LeadingZero); }
+    let mut v = 0u32;
+    for &cu in part {
+        if !(u16::from(b'0') ..= u16::from(b'9')).contains(&cu) { return Err(PackageVersionError::InvalidDigit); }
+        v = v * 10 + u32::from(cu - u16::from(b'0'));
+        if v > u32::from(u16::MAX) { return Err(PackageVersionError::PartOutOfRange); }
+    }
+    Ok(v as u16)
+}
+
+impl Debug              for PackageVersion { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { write!(fmt, "PackageVersion({})", self) } }
+impl Display            for PackageVersion { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { write!(fmt, "{}.{}.{}.{}", self.major, self.minor, self.build, self.revision) } }
+#[cfg(feature = "serde")] impl Serialize          for PackageVersion { fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> { serializer.collect_str(self) } }
+#[cfg(feature = "serde")] impl<'de> Deserialize<'de> for PackageVersion { fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> { String::deserialize(deserializer)?.parse().map_err(de::Error::custom) } }
+impl FromStr            for PackageVersion { fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_units(&s.encode_utf16().collect::<Vec<u16>>()) } type Err = PackageVersionError; }
+impl TryFrom<&[u16]>    for PackageVersion { fn try_from(units: &[u16]) -> Result<Self, Self::Error> { Self::from_units(units) } type Error = PackageVersionError; }
+impl TryFrom<&str>      for PackageVersion { fn try_from(s: &str) -> Result<Self, Self::Error> { s.parse() } type Error = PackageVersionError; }
+impl From<u64>          for PackageVersion { fn from(v: u64) -> Self { Self::from_u64(v) } }
+impl From<PackageVersion> for u64          { fn from(v: PackageVersion) -> Self { v.to_u64() } }
+
+
+
+/// A [PackageVersion] failed to parse
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PackageVersionError {
+    /// Fewer than four `.` separated parts
+    TooFewParts,
+    /// More than four `.` separated parts
+    TooManyParts,
+    /// A part was empty (e.g. `1..2.3`)
+    EmptyPart,
+    /// A part contained something other than `0`-`9`
+    InvalidDigit,
+    /// A part had a leading `0` (e.g. `1.02.3.4`)
+    LeadingZero,
+    /// A part was greater than `65535`
+    PartOutOfRange,
+}
+
+impl Error   for PackageVersionError {}
+impl Display for PackageVersionError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            PackageVersionError::TooFewParts    => "package version has fewer than 4 parts",
+            PackageVersionError::TooManyParts   => "package version has more than 4 parts",
+            PackageVersionError::EmptyPart      => "package version has an empty part",
+            PackageVersionError::InvalidDigit   => "package version has a non-decimal digit",
+            PackageVersionError::LeadingZero    => "package version has a part with a leading zero",
+            PackageVersionError::PartOutOfRange => "package version has a part greater than 65535",
+        })
+    }
+}
+
+
+
+/// Compare two sets of version code units numerically if both parse, ordinally (ignoring case) otherwise (valid versions sort last)
+pub(crate) fn cmp_units(a: &[u16], b: &[u16]) -> Ordering {
+    match (PackageVersion::from_units(a), PackageVersion::from_units(b)) {
+        (Ok(a),  Ok(b) ) => a.cmp(&b),
+        (Ok(_),  Err(_)) => Ordering::Greater,
+        (Err(_), Ok(_) ) => Ordering::Less,
+        (Err(_), Err(_)) => crate::ordinal::cmp_ignore_case(a, b),
+    }
+}
+
+#[test] fn test_version() {
+    let v : PackageVersion = "1000.19041.423.0".parse().unwrap();
+    assert_eq!(v, PackageVersion::new(1000, 19041, 423, 0));
+    assert_eq!(v.to_string(), "1000.19041.423.0");
+    assert_eq!(PackageVersion::from_u64(0x000A_0000_4A61_0000), PackageVersion::new(10, 0, 19041, 0));
+    assert_eq!(PackageVersion::new(10, 0, 19041, 0).to_u64(), 0x000A_0000_4A61_0000);
+
+    assert!("10.0.0.0".parse::<PackageVersion>().unwrap() > "9.0.0.0".parse::<PackageVersion>().unwrap());
+    assert!("1.2.3.10".parse::<PackageVersion>().unwrap() > "1.2.3.9".parse::<PackageVersion>().unwrap());
+    assert_eq!("65535.65535.65535.65535".parse::<PackageVersion>(), Ok(PackageVersion::new(65535, 65535, 65535, 65535)));
+
+    assert_eq!("1.2.3"      .parse::<PackageVersion>(), Err(PackageVersionError::TooFewParts));
+    assert_eq!(""           .parse::<PackageVersion>(), Err(PackageVersionError::EmptyPart));
+    assert_eq!("1.2.3.4.5"  .parse::<PackageVersion>(), Err(PackageVersionError::TooManyParts));
+    assert_eq!("1..3.4"     .parse::<PackageVersion>(), Err(PackageVersionError::EmptyPart));
+    assert_eq!("1.2.3.x"    .parse::<PackageVersion>(), Err(PackageVersionError::InvalidDigit));
+    assert_eq!("1.2.3.+4"   .parse::<PackageVersion>(), Err(PackageVersionError::InvalidDigit));
+    assert_eq!(" 1.2.3.4"   .parse::<PackageVersion>(), Err(PackageVersionError::InvalidDigit));
+    assert_eq!("1.02.3.4"   .parse::<PackageVersion>(), Err(PackageVersionError::LeadingZero));
+    assert_eq!("1.2.3.65536".parse::<PackageVersion>(), Err(PackageVersionError::PartOutOfRange));
+
+    assert_eq!(cmp_units(wchar::wch!("10.0.0.0"), wchar::wch!("9.0.0.0")), Ordering::Greater);
+    assert_eq!(cmp_units(wchar::wch!("garbage"),  wchar::wch!("9.0.0.0")), Ordering::Less);
+}
@@ -0,0 +1,285 @@
+//! Watch the package repository for installs, uninstalls and updates.
+//!
+//! A [RepositoryWatcher] waits on a [ChangeSource] for a hint that the repository *might* have changed, then captures a fresh
+//! [RepositorySnapshot] and diffs it against the last one to produce [RepositoryEvent]s.  Spurious wakeups are harmless.
+//!
+//! | Source                    | Wakes up |
+//! | ------------------------- | -------- |
+//! | [RegistryNotifySource]    | When `HKCR\...\AppModel\Repository\Packages` changes, via [RegNotifyChangeKeyValue] (Windows only)
+//! | [PollingSource]           | Every `interval` - for other platforms, or for other [RegistryBackend]s like [MemoryRegistry](crate::registry::MemoryRegistry)
+//!
+//! [RegNotifyChangeKeyValue]:  https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regnotifychangekeyvalue
+
+use crate::{PackageFamilyName, PackageFullName, RepositorySnapshot};
+use crate::registry::{RegistryBackend, Win32Registry};
+
+use std::io;
+use std::time::{Duration, Instant};
+
+
+
+/// A change to the package repository
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RepositoryEvent {
+    /// A family appeared (before its [PackageAdded](Self::PackageAdded) events)
+    FamilyAdded(PackageFamilyName),
+    /// A family disappeared (after its [PackageRemoved](Self::PackageRemoved) events)
+    FamilyRemoved(PackageFamilyName),
+    /// A package was installed
+    PackageAdded(PackageFullName),
+    /// A package was uninstalled
+    PackageRemoved(PackageFullName),
+    /// A package was replaced by another version of itself (`old != new`), or its properties changed in place (`old == new`)
+    PackageChanged { old: PackageFullName, new: PackageFullName },
+}
+
+/// Something that can wait for the package repository to (maybe) change
+pub trait ChangeSource {
+    /// Block until the repository may have changed (`Ok(true)`), or until `timeout` elapses (`Ok(false)`).  [None] waits forever.
+    fn wait(&mut self, timeout: Option<Duration>) -> io::Result<bool>;
+}
+
+/// A [ChangeSource] that simply wakes up every `interval`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PollingSource { interval: Duration }
+
+impl PollingSource {
+    /// Wake up every `interval`
+    pub fn new(interval: Duration) -> Self { Self { interval } }
+}
+
+impl ChangeSource for PollingSource {
+    fn wait(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
+        match timeout {
+            Some(timeout) if timeout < self.interval    => { std::thread::sleep(timeout); Ok(false) },
+            _                                           => { std::thread::sleep(self.interval); Ok(true) },
+        }
+    }
+}
+
+/// A [ChangeSource] signaled by [RegNotifyChangeKeyValue] on the live `HKCR\...\AppModel\Repository\Packages` key.
+/// Returns [io::ErrorKind::Unsupported] on non-Windows platforms.
+///
+/// [RegNotifyChangeKeyValue]:  https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regnotifychangekeyvalue
+pub struct RegistryNotifySource(imp::NotifySource);
+
+impl RegistryNotifySource {
+    /// Start watching `HKCR\...\AppModel\Repository\Packages`
+    pub fn new() -> io::Result<Self> { imp::NotifySource::new().map(Self) }
+}
+
+impl ChangeSource for RegistryNotifySource {
+    fn wait(&mut self, timeout: Option<Duration>) -> io::Result<bool> { self.0.wait(timeout) }
+}
+
+/// Reports [RepositoryEvent]s by diffing [RepositorySnapshot]s whenever a [ChangeSource] wakes up
+///
+/// ### Examples
+///
+/// ```rust,no_run
+/// let mut watcher = appx::watch::RepositoryWatcher::new().unwrap();
+/// loop {
+///     for event in watcher.wait(None).unwrap() {
+///         println!("{:?}", event);
+///     }
+/// }
+/// ```
+pub struct RepositoryWatcher {
+    registry:   Box<dyn RegistryBackend>,
+    source:     Box<dyn ChangeSource>,
+    last:       RepositorySnapshot,
+}
+
+impl RepositoryWatcher {
+    /// Watch the live registry of this computer with a [RegistryNotifySource] on Windows, or a 5 second [PollingSource] elsewhere
+    /// (which is only useful once the live registry is supported there - until then this returns [io::ErrorKind::Unsupported].)
+    pub fn new() -> io::Result<Self> {
+        if cfg!(windows) {
+            Self::with_source(Win32Registry, RegistryNotifySource::new()?)
+        } else {
+            Self::with_source(Win32Registry, PollingSource::new(Duration::from_secs(5)))
+        }
+    }
+
+    /// Watch `registry`, rechecking it whenever `source` wakes up.  Captures the initial snapshot that later events are relative to.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use appx::registry::MemoryRegistry;
+    /// use appx::watch::*;
+    /// use std::time::Duration;
+    ///
+    /// let reg = MemoryRegistry::new();
+    /// reg.create_key(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Families");
+    /// let mut watcher = RepositoryWatcher::with_source(reg.clone(), PollingSource::new(Duration::from_millis(1))).unwrap();
+    /// assert!(watcher.wait(Some(Duration::from_millis(10))).unwrap().is_empty());
+    ///
+    /// reg.create_key(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Families\Foo_8wekyb3d8bbwe\Foo_1.0.0.0_x64__8wekyb3d8bbwe");
+    /// assert_eq!(watcher.wait(None).unwrap().len(), 2); // FamilyAdded, PackageAdded
+    /// ```
+    pub fn with_source(registry: impl RegistryBackend + 'static, source: impl ChangeSource + 'static) -> io::Result<Self> {
+        let last = RepositorySnapshot::capture_in(&registry)?;
+        Ok(Self { registry: Box::new(registry), source: Box::new(source), last })
+    }
+
+    /// The snapshot events are currently relative to
+    pub fn snapshot(&self) -> &RepositorySnapshot { &self.last }
+
+    /// Recheck the repository right now (without waiting on the [ChangeSource]), returning any changes since the last check
+    pub fn poll(&mut self) -> io::Result<Vec<RepositoryEvent>> {
+        let next = RepositorySnapshot::capture_in(&*self.registry)?;
+        let events = events(&self.last, &next);
+        self.last = next;
+        Ok(events)
+    }
+
+    /// Wait for changes, returning them as soon as there are any, or an empty list if `timeout` elapses first.  [None] waits forever.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Vec<RepositoryEvent>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if !self.source.wait(remaining)? { return Ok(Vec::new()); }
+            let events = self.poll()?;
+            if !events.is_empty() { return Ok(events); }
+        }
+    }
+}
+
+fn events(old: &RepositorySnapshot, new: &RepositorySnapshot) -> Vec<RepositoryEvent> {
+    let mut events = Vec::new();
+    let old_families = old.families().collect::<Vec<_>>();
+    let new_families = new.families().collect::<Vec<_>>();
+
+    events.extend(new_families.iter().filter(|f| !old_families.contains(f)).map(|&f| RepositoryEvent::FamilyAdded(f.clone())));
+    for family in old.diff(new).families() {
+        events.extend(family.removed().iter().map(|pfn| RepositoryEvent::PackageRemoved(pfn.clone())));
+        events.extend(family.added()  .iter().map(|pfn| RepositoryEvent::PackageAdded(pfn.clone())));
+        events.extend(family.version_changed().iter().map(|(old, new)| RepositoryEvent::PackageChanged { old: old.clone(), new: new.clone() }));
+    }
+    for pkg in new.packages() {
+        let family = pkg.full_name().family_name();
+        if let Some(prev) = old.packages_for_family(&family).iter().find(|prev| prev.full_name() == pkg.full_name()) {
+            if prev != pkg { events.push(RepositoryEvent::PackageChanged { old: prev.full_name().clone(), new: pkg.full_name().clone() }); }
+        }
+    }
+    events.extend(old_families.iter().filter(|f| !new_families.contains(f)).map(|&f| RepositoryEvent::FamilyRemoved(f.clone())));
+    events
+}
+
+
+
+#[cfg(not(windows))] mod imp {
+    use super::*;
+
+    pub(super) enum NotifySource {}
+
+    impl NotifySource {
+        pub fn new() -> io::Result<Self> { Err(io::Error::new(io::ErrorKind::Unsupported, "RegistryNotifySource: not implemented on this platform")) }
+        pub fn wait(&mut self, _timeout: Option<Duration>) -> io::Result<bool> { match *self {} }
+    }
+}
+
+
+
+#[cfg(windows)] mod imp {
+    use super::*;
+    use crate::reg::{Hkey, Options, SAM};
+    use winapi::shared::winerror::WAIT_TIMEOUT;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::synchapi::{CreateEventW, WaitForSingleObject};
+    use winapi::um::winbase::{INFINITE, WAIT_OBJECT_0};
+    use winapi::um::winnt::HANDLE;
+    use std::convert::TryFrom;
+    use std::ptr::null_mut;
+
+    pub(super) struct NotifySource {
+        key:    Hkey,
+        event:  HANDLE,
+    }
+
+    impl NotifySource {
+        pub fn new() -> io::Result<Self> {
+            let path = crate::repository::PACKAGES.iter().copied().chain(Some(0)).collect::<Vec<u16>>();
+            let key = Hkey::hkcr(&path, Options::NONE, SAM::NOTIFY)?;
+            let event = unsafe { CreateEventW(null_mut(), 0, 0, null_mut()) }; // auto-reset, initially unsignaled
+            if event.is_null() { return Err(io::Error::last_os_error()); }
+            let source = Self { key, event };
+            unsafe { source.key.notify_change_key_value(source.event.cast()) }?;
+            Ok(source)
+        }
+
+        pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
+            let ms = timeout.map_or(INFINITE, |t| u32::try_from(t.as_millis()).unwrap_or(INFINITE - 1).min(INFINITE - 1));
+            match unsafe { WaitForSingleObject(self.event, ms) } {
+                WAIT_OBJECT_0   => { unsafe { self.key.notify_change_key_value(self.event.cast()) }?; Ok(true) },
+                WAIT_TIMEOUT    => Ok(false),
+                _               => Err(io::Error::last_os_error()),
+            }
+        }
+    }
+
+    impl Drop for NotifySource {
+        fn drop(&mut self) {
+            // `key` is closed right after this, canceling the outstanding registration
+            unsafe { CloseHandle(self.event) };
+        }
+    }
+}
+
+
+
+#[test] fn test_repository_watcher() {
+    use crate::registry::{MemoryRegistry, RawValue};
+    use crate::repository::{test_install, FAMILIES, PACKAGES};
+
+    struct Manual(std::rc::Rc<std::cell::Cell<u32>>);
+    impl ChangeSource for Manual {
+        fn wait(&mut self, _timeout: Option<Duration>) -> io::Result<bool> {
+            let pending = self.0.get();
+            if pending > 0 { self.0.set(pending - 1); }
+            Ok(pending > 0)
+        }
+    }
+
+    let families = String::from_utf16_lossy(FAMILIES);
+    let packages = String::from_utf16_lossy(PACKAGES);
+    let reg = MemoryRegistry::new();
+    reg.create_key(&families);
+    let signals = std::rc::Rc::new(std::cell::Cell::new(0));
+    let mut watcher = RepositoryWatcher::with_source(reg.clone(), Manual(signals.clone())).unwrap();
+    let fam = PackageFamilyName::new("NcsiUwpApp_8wekyb3d8bbwe").unwrap();
+    let v1  = PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap();
+    let v2  = PackageFullName::new("NcsiUwpApp_1000.19041.424.0_neutral_neutral_8wekyb3d8bbwe").unwrap();
+
+    // no signal, no events (even though the registry changed)
+    test_install(&reg, &v1.to_string());
+    assert_eq!(watcher.wait(None).unwrap(), []);
+
+    signals.set(1);
+    assert_eq!(watcher.wait(None).unwrap(), [RepositoryEvent::FamilyAdded(fam.clone()), RepositoryEvent::PackageAdded(v1.clone())]);
+
+    // spurious signal: no events
+    signals.set(1);
+    assert_eq!(watcher.wait(None).unwrap(), []);
+
+    reg.set_value(&format!(r"{}\{}", packages, v1), "DisplayName", RawValue::sz("NcsiUwpApp"));
+    assert_eq!(watcher.poll().unwrap(), [RepositoryEvent::PackageChanged { old: v1.clone(), new: v1.clone() }]);
+
+    assert!(reg.delete_key(&format!(r"{}\{}\{}", families, fam, v1)));
+    test_install(&reg, &v2.to_string());
+    assert_eq!(watcher.poll().unwrap(), [RepositoryEvent::PackageChanged { old: v1.clone(), new: v2.clone() }]);
+
+    assert!(reg.delete_key(&format!(r"{}\{}", families, fam)));
+    assert_eq!(watcher.poll().unwrap(), [RepositoryEvent::PackageRemoved(v2.clone()), RepositoryEvent::FamilyRemoved(fam.clone())]);
+    assert_eq!(watcher.poll().unwrap(), []);
+
+    // the polling fallback
+    let mut watcher = RepositoryWatcher::with_source(reg.clone(), PollingSource::new(Duration::from_millis(1))).unwrap();
+    assert_eq!(watcher.wait(Some(Duration::from_millis(5))).unwrap(), []);
+    test_install(&reg, &v1.to_string());
+    assert_eq!(watcher.wait(Some(Duration::from_secs(60))).unwrap().len(), 2);
+
+    if !cfg!(windows) { assert_eq!(RegistryNotifySource::new().err().unwrap().kind(), io::ErrorKind::Unsupported); }
+}
@@ -0,0 +1,242 @@
+//! Reader for the registry text files of a [Wine](https://www.winehq.org/) prefix (`system.reg`, `user.reg`)
+
+use crate::registry::{MemoryRegistry, RawValue, RegistryBackend, RegistryKey, RegType};
+
+use std::io;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::CharIndices;
+
+
+
+/// The registry of a [Wine](https://www.winehq.org/) prefix (e.g. `~/.wine`), as saved to its `system.reg` and `user.reg` text files.
+///
+/// `HKEY_CLASSES_ROOT` is synthesized the same way Windows does it: `HKLM\Software\Classes` (from `system.reg`),
+/// overlaid with `HKCU\Software\Classes` (from `user.reg`).  Keys outside of `Software\Classes` are ignored.
+///
+/// The files are read once: changes Wine makes afterwards aren't seen (Wine also only flushes them periodically and on `wineserver -k`.)
+///
+/// ### Examples
+///
+/// ```rust,no_run
+/// let wine = appx::registry::WineRegistry::open_prefix(std::path::Path::new(&std::env::var("HOME").unwrap()).join(".wine")).unwrap();
+/// for pkg in appx::repository::packages_in(&wine).unwrap() {
+///     println!("{}", pkg.unwrap());
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct WineRegistry(MemoryRegistry);
+
+impl WineRegistry {
+    /// Read `system.reg` and `user.reg` from the Wine prefix directory `prefix`.  Either file may be missing, but not both.
+    pub fn open_prefix(prefix: impl AsRef<Path>) -> io::Result<Self> {
+        let prefix = prefix.as_ref();
+        let system  = read_optional(&prefix.join("system.reg"))?;
+        let user    = read_optional(&prefix.join("user.reg"))?;
+        if system.is_none() && user.is_none() { return Err(io::Error::new(io::ErrorKind::NotFound, "wine prefix has neither `system.reg` nor `user.reg`")); }
+        Self::from_reg_files(system.as_deref().unwrap_or(""), user.as_deref().unwrap_or(""))
+    }
+
+    /// Parse the contents of `system.reg` and `user.reg` (pass `""` for a missing file)
+    pub fn from_reg_files(system_reg: &str, user_reg: &str) -> io::Result<Self> {
+        let reg = MemoryRegistry::new();
+        for text in [system_reg, user_reg].iter().filter(|text| !text.is_empty()) {
+            parse(text, &reg)?;
+        }
+        Ok(Self(reg))
+    }
+}
+
+impl RegistryBackend for WineRegistry {
+    fn open(&self, path: &[u16]) -> io::Result<Box<dyn RegistryKey>> { self.0.open(path) }
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<String>> {
+    match std::fs::read(path) {
+        Ok(bytes)                                           => Ok(Some(String::from_utf8_lossy(&bytes).into_owned())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound   => Ok(None),
+        Err(err)                                            => Err(err),
+    }
+}
+
+/// `Software\Classes\Foo\Bar` → `Foo\Bar`
+fn strip_classes(key: &[u16]) -> Option<&[u16]> {
+    let prefix = wchar::wch!(r"Software\Classes");
+    if key.len() < prefix.len() || !crate::ordinal::eq_ignore_case(&key[..prefix.len()], prefix) { return None; }
+    match key.get(prefix.len()) {
+        None                                => Some(&[]),
+        Some(&cu) if cu == u16::from(b'\\') => Some(&key[prefix.len()+1..]),
+        Some(_)                             => None,
+    }
+}
+
+
+
+/// Parse a Wine registry file, adding everything under `Software\Classes` to `reg`
+fn parse(text: &str, reg: &MemoryRegistry) -> io::Result<()> {
+    let mut lines = text.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim_end() == "WINE REGISTRY Version 2" => {},
+        _ => return Err(invalid_data(0, "expected `WINE REGISTRY Version 2` header")),
+    }
+
+    let mut key = None;
+    while let Some((n, line)) = lines.next() {
+        // hex data may be continued across lines with a trailing `\`
+        let mut line = line.trim_end().to_string();
+        while line.ends_with('\\') && !line.trim_start().starts_with('[') {
+            line.pop();
+            match lines.next() {
+                Some((_, next)) => line.push_str(next.trim()),
+                None            => break,
+            }
+        }
+        let line = line.trim_start();
+
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue; // blank, comment, or option (`#time=...`, `#arch=...`, `#class=...`, `#link`)
+        } else if let Some(rest) = line.strip_prefix('[') {
+            let (name, _timestamp) = unescape(rest, ']').ok_or_else(|| invalid_data(n, "unterminated key name"))?;
+            if let Some(classes) = strip_classes(&name) { reg.create_key_units(classes); }
+            key = Some(name);
+        } else {
+            let key = key.as_ref().ok_or_else(|| invalid_data(n, "value before the first key"))?;
+            let (name, rest) = if let Some(rest) = line.strip_prefix('@') {
+                (Vec::new(), rest)
+            } else if let Some(rest) = line.strip_prefix('"') {
+                unescape(rest, '"').ok_or_else(|| invalid_data(n, "unterminated value name"))?
+            } else {
+                return Err(invalid_data(n, "expected a `[key]`, `\"name\"=...`, or `@=...`"));
+            };
+            let data = rest.trim_start().strip_prefix('=').ok_or_else(|| invalid_data(n, "expected `=` after value name"))?;
+            let value = parse_data(data.trim()).ok_or_else(|| invalid_data(n, "malformed value data"))?;
+            if let Some(classes) = strip_classes(key) { reg.set_value_units(classes, &name, value); }
+        }
+    }
+    Ok(())
+}
+
+/// `"..."`, `str(N):"..."`, `dword:XXXXXXXX`, `hex:XX,XX,...`, or `hex(N):XX,XX,...`
+fn parse_data(data: &str) -> Option<RawValue> {
+    if let Some(rest) = data.strip_prefix('"') {
+        string_value(RegType::SZ, rest)
+    } else if let Some(rest) = data.strip_prefix("str(") {
+        let (ty, rest) = rest.split_once("):\"")?;
+        string_value(RegType(u32::from_str_radix(ty, 16).ok()?), rest)
+    } else if let Some(rest) = data.strip_prefix("dword:") {
+        Some(RawValue::dword(u32::from_str_radix(rest.trim(), 16).ok()?))
+    } else if let Some(rest) = data.strip_prefix("hex:") {
+        Some(RawValue { ty: RegType::BINARY, data: hex_bytes(rest)? })
+    } else if let Some(rest) = data.strip_prefix("hex(") {
+        let (ty, rest) = rest.split_once("):")?;
+        Some(RawValue { ty: RegType(u32::from_str_radix(ty, 16).ok()?), data: hex_bytes(rest)? })
+    } else {
+        None
+    }
+}
+
+/// Wine strips the `\0` terminator from string data when saving - add it back
+fn string_value(ty: RegType, rest: &str) -> Option<RawValue> {
+    let (units, rest) = unescape(rest, '"')?;
+    if !rest.trim().is_empty() { return None; }
+    Some(RawValue { ty, data: units.into_iter().chain(Some(0)).flat_map(u16::to_le_bytes).collect() })
+}
+
+fn hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    hex.split(',').map(str::trim).filter(|b| !b.is_empty()).map(|b| u8::from_str_radix(b, 16).ok()).collect()
+}
+
+/// Unescape a string up to (but excluding) an unescaped `end`, returning the string and whatever follows `end`.
+///
+/// Handles the escapes Wine writes: `\\`, `\"`, `\a`, `\b`, `\e`, `\f`, `\n`, `\r`, `\t`, `\v`, `\xXXXX` (up to 4 hex digits), and `\ooo` (up to 3 octal digits.)
+fn unescape(s: &str, end: char) -> Option<(Vec<u16>, &str)> {
+    let mut units = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        if ch == end { return Some((units, &s[i+ch.len_utf8()..])); }
+        if ch != '\\' { let mut buf = [0u16; 2]; units.extend_from_slice(ch.encode_utf16(&mut buf)); continue; }
+
+        let (_, esc) = chars.next()?;
+        units.push(match esc {
+            'a'         => 0x07,
+            'b'         => 0x08,
+            'e'         => 0x1B,
+            'f'         => 0x0C,
+            'n'         => u16::from(b'\n'),
+            'r'         => u16::from(b'\r'),
+            't'         => u16::from(b'\t'),
+            'v'         => 0x0B,
+            'x'         => digits(&mut chars, 16, 4, 0),
+            '0'..='7'   => digits(&mut chars, 8, 2, esc.to_digit(8).unwrap()),
+            other       => { let mut buf = [0u16; 2]; units.extend_from_slice(other.encode_utf16(&mut buf)); continue; },
+        });
+    }
+    None
+}
+
+/// Continue parsing up to `max` more digits of `radix` onto `v`
+fn digits(chars: &mut Peekable<CharIndices>, radix: u32, max: usize, mut v: u32) -> u16 {
+    for _ in 0..max {
+        match chars.peek().and_then(|&(_, d)| d.to_digit(radix)) {
+            Some(d) => { v = v * radix + d; chars.next(); },
+            None    => break,
+        }
+    }
+    v as u16
+}
+
+fn invalid_data(line: usize, msg: &str) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, format!("wine registry line {}: {}", line + 1, msg)) }
+
+
+
+#[test] fn test_wine_registry() {
+    use crate::{repository, PackageFamilyName, PackageFullName};
+
+    let system_reg = r#"WINE REGISTRY Version 2
+;; All keys relative to \\Machine
+
+#arch=win64
+
+[Software\\Classes\\Local Settings\\Software\\Microsoft\\Windows\\CurrentVersion\\AppModel\\Repository\\Packages\\NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe] 1600000000
+#time=1d6a1b2c3d4e5f6
+"DisplayName"="Machine Name"
+"SupportedUsers"=dword:00000002
+
+[Software\\Wine] 1600000000
+"Version"="ignored"
+"#;
+
+    let user_reg = r#"WINE REGISTRY Version 2
+;; All keys relative to \\User\\S-1-5-21-0-0-0-1000
+
+[Software\\Classes\\Local Settings\\Software\\Microsoft\\Windows\\CurrentVersion\\AppModel\\Repository\\Families\\NcsiUwpApp_8wekyb3d8bbwe\\NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe] 1600000000
+
+[Software\\Classes\\Local Settings\\Software\\Microsoft\\Windows\\CurrentVersion\\AppModel\\Repository\\Packages\\NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe] 1600000000
+"DisplayName"="Ncsi \"Uwp\" App\\\x00e9\101"
+"OSMinVersion"=hex(b):00,00,00,00,00,00,0a,00
+"PackageRootFolder"=str(2):"%SystemRoot%\\SystemApps\\NcsiUwpApp_8wekyb3d8bbwe"
+"PackageSid"=hex:01,02,00,00,00,00,00,0f,02,00,00,00,\
+  01,00,00,00
+@="default"
+"#;
+
+    let wine = WineRegistry::from_reg_files(system_reg, user_reg).unwrap();
+    let fam = PackageFamilyName::new("NcsiUwpApp_8wekyb3d8bbwe").unwrap();
+    let pfn = PackageFullName::new("NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe").unwrap();
+    assert_eq!(repository::families_in(&wine).unwrap().collect::<io::Result<Vec<_>>>().unwrap(), vec![fam.clone()]);
+    assert_eq!(repository::packages_in(&wine).unwrap().collect::<io::Result<Vec<_>>>().unwrap(), vec![pfn.clone()]);
+    assert_eq!(repository::packages_for_family_in(&wine, &fam).unwrap().collect::<io::Result<Vec<_>>>().unwrap(), vec![pfn.clone()]);
+
+    assert_eq!(pfn.display_name_in(&wine).unwrap(),     "Ncsi \"Uwp\" App\\\u{e9}A"); // user.reg overrides system.reg
+    assert_eq!(pfn.supported_users_in(&wine).unwrap(),  2);
+    assert_eq!(pfn.os_min_version_in(&wine).unwrap(),   0x000A_0000_0000_0000);
+    assert_eq!(pfn.package_sid_in(&wine).unwrap().to_string(), "S-1-15-2-1");
+    assert_eq!(wine.open(wchar::wch!(r"Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages\NcsiUwpApp_1000.19041.423.0_neutral_neutral_8wekyb3d8bbwe")).unwrap()
+        .value(wchar::wch!("PackageRootFolder")).unwrap().ty, RegType::EXPAND_SZ);
+    assert!(wine.open(wchar::wch!(r"Wine")).is_err());
+
+    assert!(WineRegistry::from_reg_files("REGEDIT4\n", "").is_err());
+    assert!(WineRegistry::from_reg_files("WINE REGISTRY Version 2\n\"Value\"=dword:1\n", "").is_err());
+    assert!(WineRegistry::from_reg_files("WINE REGISTRY Version 2\n[Software\\\\Classes]\n\"Value\"=dword:xyz\n", "").is_err());
+    assert!(WineRegistry::from_reg_files("WINE REGISTRY Version 2\n[Software\\\\Classes]\n\"Value\"=\"unterminated\n", "").is_err());
+}
@@ -47,6 +47,9 @@ impl WString {
     /// Length of the string in [code units](https://unicode.org/glossary/#code_unit), without the `\0`-terminator
     pub fn len(&self) -> usize { self.units().len() }
 
+    /// Returns `true` if the string contains no [code units](https://unicode.org/glossary/#code_unit) (besides the `\0`-terminator)
+    pub fn is_empty(&self) -> bool { self.units().is_empty() }
+
     /// Length of the string in [code units](https://unicode.org/glossary/#code_unit), **including** the `\0`-terminator
     pub fn len0(&self) -> usize { self.units0().len() }
 
@@ -90,7 +93,7 @@ impl From< Vec<u16>> for WString { fn from(value:  Vec<u16>) -> Self { Self::fro
 }
 
 #[cfg(not(windows))] impl WString {
-    fn to_os_string_impl(&self) -> OsString { OsString::from(String::from_utf16_lossy(self.units0())) } // best effort
+    fn to_os_string_impl(&self) -> OsString { OsString::from(String::from_utf16_lossy(self.units())) } // best effort
 }
 
 /// This module exists for easier auditing/code reviews, by limiting access to: